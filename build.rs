@@ -3,6 +3,10 @@ use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 
+#[allow(dead_code)]
+#[path = "src/sar.rs"]
+mod sar;
+
 const SCITER_SDK_URL: &str =
     "https://github.com/c-smile/sciter-js-sdk/archive/refs/heads/main.zip";
 
@@ -18,7 +22,7 @@ fn locate_sciter_sdk() -> PathBuf {
     // 1. Explicit environment variable
     if let Ok(dir) = std::env::var("SCITER_SDK_DIR") {
         let p = PathBuf::from(&dir);
-        if p.join("bin/windows/packfolder.exe").exists() {
+        if p.join("bin/windows/x64/sciter.dll").exists() {
             println!("cargo:warning=Using Sciter SDK from SCITER_SDK_DIR: {}", dir);
             return p;
         }
@@ -29,7 +33,7 @@ fn locate_sciter_sdk() -> PathBuf {
     let local_sdk = manifest_dir.join(".sciter-sdk").join("sciter-js-sdk-main");
 
     // 2. Local cached download
-    if local_sdk.join("bin/windows/packfolder.exe").exists() {
+    if local_sdk.join("bin/windows/x64/sciter.dll").exists() {
         println!(
             "cargo:warning=Using cached Sciter SDK at {}",
             local_sdk.display()
@@ -39,7 +43,7 @@ fn locate_sciter_sdk() -> PathBuf {
 
     // 3. Legacy fixed path
     let legacy = PathBuf::from(r"C:\sciter-js-sdk-main");
-    if legacy.join("bin/windows/packfolder.exe").exists() {
+    if legacy.join("bin/windows/x64/sciter.dll").exists() {
         println!("cargo:warning=Using Sciter SDK at legacy path C:\\sciter-js-sdk-main");
         return legacy;
     }
@@ -47,9 +51,9 @@ fn locate_sciter_sdk() -> PathBuf {
     // None found – download
     download_sciter_sdk(&manifest_dir.join(".sciter-sdk"));
     assert!(
-        local_sdk.join("bin/windows/packfolder.exe").exists(),
-        "Sciter SDK download succeeded but packfolder.exe not found at {}",
-        local_sdk.join("bin/windows/packfolder.exe").display()
+        local_sdk.join("bin/windows/x64/sciter.dll").exists(),
+        "Sciter SDK download succeeded but sciter.dll not found at {}",
+        local_sdk.join("bin/windows/x64/sciter.dll").display()
     );
     local_sdk
 }
@@ -120,85 +124,50 @@ fn download_sciter_sdk(dest_dir: &std::path::Path) {
 fn main() {
     println!("cargo:rerun-if-changed=src/ui");
     println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed=scripts");
+    println!("cargo:rerun-if-changed=tools");
 
-    if std::env::var_os("CARGO_CFG_WINDOWS").is_some() {
-        let sdk_dir = locate_sciter_sdk();
-        let packfolder = sdk_dir.join("bin/windows/packfolder.exe");
-        let sciter_dll = sdk_dir.join("bin/windows/x64/sciter.dll");
-
-        println!("cargo:rerun-if-changed={}", sciter_dll.display());
-
-        let status = Command::new(&packfolder)
-            .arg("./src/ui")
-            .arg("./src/ui.rc")
-            .arg("-binary")
-            .status()
-            .unwrap_or_else(|e| {
-                panic!(
-                    "failed to execute packfolder.exe at {}: {}",
-                    packfolder.display(),
-                    e
-                )
-            });
-
-        if !status.success() {
-            panic!("packfolder.exe failed to generate src/ui.rc");
-        }
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR env var is not set"));
 
-        if !sciter_dll.exists() {
-            panic!(
-                "sciter.dll not found at {}",
-                sciter_dll.display()
-            );
-        }
+    // Empacotar src/ui no formato de arquivo do Sciter, em qualquer sistema
+    if std::env::var_os("CARGO_FEATURE_GUI").is_some() {
+        let archive = sar::pack_dir(&manifest_dir.join("src").join("ui"))
+            .unwrap_or_else(|e| panic!("failed to pack src/ui: {}", e));
+        let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("OUT_DIR env var is not set"));
+        // Embutido no executavel por `include_bytes!` em main.rs
+        fs::write(out_dir.join("ui.rc"), &archive).expect("failed to write ui.rc to OUT_DIR");
+    }
 
+    if std::env::var_os("CARGO_CFG_WINDOWS").is_some() {
         let profile = std::env::var("PROFILE").expect("PROFILE env var is not set");
         let target_root = std::env::var_os("CARGO_TARGET_DIR")
             .map(PathBuf::from)
-            .unwrap_or_else(|| {
-                PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR env var is not set")).join("target")
-            });
+            .unwrap_or_else(|| manifest_dir.join("target"));
         let target_profile_dir = target_root.join(&profile);
         fs::create_dir_all(&target_profile_dir).expect("failed to create target profile directory");
-        fs::copy(&sciter_dll, target_profile_dir.join("sciter.dll"))
-            .expect("failed to copy sciter.dll to target profile directory");
-
-        // Copiar pasta scripts para o diretório de saída se existir
-        let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
-        let scripts_src = manifest_dir.join("scripts");
-        if scripts_src.exists() {
-            let scripts_dst = target_profile_dir.join("scripts");
-            let _ = fs::remove_dir_all(&scripts_dst); // limpar primeiro
-            copy_dir_recursive(&scripts_src, &scripts_dst)
-                .expect("failed to copy scripts directory");
-        }
 
-        // Copiar ui.rc para o diretório de saída
-        let ui_rc_src = manifest_dir.join("src").join("ui.rc");
-        if ui_rc_src.exists() {
-            fs::copy(&ui_rc_src, target_profile_dir.join("ui.rc"))
-                .expect("failed to copy ui.rc to target directory");
-        }
+        // O Sciter so e necessario para a janela do launcher (feature `gui`)
+        if std::env::var_os("CARGO_FEATURE_GUI").is_some() {
+            let sdk_dir = locate_sciter_sdk();
+            let sciter_dll = sdk_dir.join("bin/windows/x64/sciter.dll");
 
-        // Copiar reguinha.exe para o diretório de saída
-        let reguinha_src = manifest_dir.join("reguinha.exe");
-        if reguinha_src.exists() {
-            fs::copy(&reguinha_src, target_profile_dir.join("reguinha.exe"))
-                .expect("failed to copy reguinha.exe to target directory");
-        }
+            println!("cargo:rerun-if-changed={}", sciter_dll.display());
 
-        // Inicializar banco de dados com conta de teste
-        let db_path = target_profile_dir.join("userdata.redb");
-        // Remover banco existente se for build release para sempre ter conta de teste fresca
-        if profile == "release" && db_path.exists() {
-            let _ = fs::remove_file(&db_path);
-        }
-        if !db_path.exists() {
-            println!("Criando banco de dados com conta de teste: {:?}", db_path);
-            if let Err(e) = initialize_database(&db_path) {
-                eprintln!("Aviso: Erro ao criar banco de dados: {}", e);
+            if !sciter_dll.exists() {
+                panic!(
+                    "sciter.dll not found at {}",
+                    sciter_dll.display()
+                );
             }
+
+            fs::copy(&sciter_dll, target_profile_dir.join("sciter.dll"))
+                .expect("failed to copy sciter.dll to target profile directory");
+        }
+
+        // Copiar ferramentas externas (régua, etc.) para o diretório de saída
+        let tools_src = manifest_dir.join("tools");
+        if tools_src.exists() {
+            copy_dir_recursive(&tools_src, &target_profile_dir.join("tools"))
+                .expect("failed to copy tools directory");
         }
 
         embed_manifest(new_manifest("Contoso.Sample"))
@@ -222,32 +191,3 @@ fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> std::io::Result<()> {
     }
     Ok(())
 }
-
-fn initialize_database(db_path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    use redb::TableDefinition;
-    
-    const TABLE: TableDefinition<&str, &str> = TableDefinition::new("users");
-    
-    let db = redb::Database::create(db_path)?;
-    let write_txn = db.begin_write()?;
-    {
-        let mut table = write_txn.open_table(TABLE)?;
-        
-        // Adicionar conta de teste
-        let test_uuid = "00000000-0000-0000-0000-000000000001";
-        let test_account = r#"{
-            "username": "usuario_teste",
-            "password": "senha123",
-            "strategy": "337.lua",
-            "server": "10000",
-            "nickname": "Conta Teste"
-        }"#;
-        
-        table.insert(test_uuid, test_account)?;
-        println!("Conta de teste adicionada ao banco de dados!");
-    }
-    write_txn.commit()?;
-    
-    println!("Banco de dados inicializado com sucesso!");
-    Ok(())
-}
@@ -1,5 +1,18 @@
 -- Strategy: 337.com ddtank
 -- Comment: Login via 337.com API
+-- Launcher: system
+
+function servers()
+    return {
+        { id = "10000", name = "Ilha dos valentões", range = "S1-3, 9-10, 12-19" },
+        { id = "10001", name = "Vale dos Ouriços", range = "S4-8, 11, 20-46" },
+        { id = "10005", name = "Jogos Olímpicos", range = "S47-131, 362-375" },
+        { id = "10006", name = "Lugares escuros", range = "S132-394" },
+        { id = "10031", name = "Universo DDToker", range = "S395-398" },
+        { id = "10090", name = "Legado dos Campeões", range = "S399" },
+        { id = "10091", name = "Aurora", range = "S400" },
+    }
+end
 
 function login(username, password, server_id)
     local agent = agent()
@@ -10,8 +23,8 @@ function login(username, password, server_id)
     -- Step 1: Login via API
     local login_url = string.format(
         "https://www.337.com/api.php?a=1002&username=%s&password=%s",
-        username,
-        password
+        url_encode(username),
+        url_encode(password)
     )
     
     local login_response = agent:get(login_url)
@@ -19,7 +32,9 @@ function login(username, password, server_id)
     -- Verificar se o login foi bem-sucedido
     local error_code = string.match(login_response, [["error":(%d+)]])
     if not error_code or error_code ~= "0" then
-        error("Falha no login. Resposta: " .. login_response)
+        -- Nunca incluir a resposta inteira: ela pode conter tokens de sessao
+        local message = string.match(login_response, [["msg":"([^"]*)"]])
+        error("Falha no login (codigo " .. tostring(error_code) .. ")" .. (message and (": " .. message) or ""))
     end
 
     -- Step 2: Acessar página principal novamente para confirmar login
@@ -42,8 +57,6 @@ function login(username, password, server_id)
         error("Falha ao extrair URL do jogo. A página pode ter mudado. Response size: " .. #play_page)
     end
     
-    -- Abrir URL no navegador padrão do sistema (com roadclient://)
-    os.execute('start "" "' .. game_url .. '"')
-
-    return "Abrindo jogo no navegador..."
+    -- O launcher configurado (por padrão o handler roadclient:// do sistema) abre o jogo
+    return game_url
 end
@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::redact::{redact_cookie_values, redact_with, Secrets};
+
+/// Where strategy HTTP traffic goes: recorded to a cassette file while hitting
+/// the live server, or replayed from one without touching the network.
+#[derive(Debug, Clone)]
+pub enum CassetteMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Cookies the jar would send with this request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cookies: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    /// Final URL after redirects.
+    pub url: String,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// An ordered list of HTTP interactions made by one strategy run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read cassette {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Hide `secrets` in any encoding, cookie values and everything the logs
+    /// hide (session URLs, tokens, ...) in every URL, body and header, so the
+    /// cassette can be attached to a bug report.
+    pub fn redact(&mut self, secrets: &Secrets) {
+        let scrub = |text: &mut String| *text = redact_with(text, secrets);
+
+        for interaction in &mut self.interactions {
+            let request = &mut interaction.request;
+            scrub(&mut request.url);
+            if let Some(body) = request.body.as_mut() {
+                scrub(body);
+            }
+            request.cookies = request.cookies.as_deref().map(redact_cookie_values);
+            redact_headers(&mut request.headers, &scrub);
+
+            let response = &mut interaction.response;
+            scrub(&mut response.url);
+            scrub(&mut response.body);
+            redact_headers(&mut response.headers, &scrub);
+        }
+    }
+}
+
+fn redact_headers<F: Fn(&mut String)>(headers: &mut [(String, String)], scrub: &F) {
+    for (name, value) in headers.iter_mut() {
+        if name.eq_ignore_ascii_case("cookie") || name.eq_ignore_ascii_case("set-cookie") {
+            *value = redact_cookie_values(value);
+        } else {
+            scrub(value);
+        }
+    }
+}
+
+/// Shared by every `agent()` created during one strategy run.
+pub(crate) struct Tape {
+    mode: CassetteMode,
+    cassette: Cassette,
+    cursor: usize,
+    secrets: Secrets,
+}
+
+impl Tape {
+    pub(crate) fn open(mode: &CassetteMode, secrets: &[&str]) -> Result<Self> {
+        let cassette = match mode {
+            CassetteMode::Record(_) => Cassette::default(),
+            CassetteMode::Replay(path) => Cassette::load(path)?,
+        };
+        Ok(Self {
+            mode: mode.clone(),
+            cassette,
+            cursor: 0,
+            secrets: Secrets::new(secrets),
+        })
+    }
+
+    pub(crate) fn is_replay(&self) -> bool {
+        matches!(self.mode, CassetteMode::Replay(_))
+    }
+
+    pub(crate) fn record(&mut self, interaction: Interaction) {
+        self.cassette.interactions.push(interaction);
+    }
+
+    /// Next recorded response. The live URL is redacted the same way the
+    /// cassette was, so a replay with different credentials still matches.
+    pub(crate) fn replay(&mut self, method: &str, url: &str) -> Result<RecordedResponse> {
+        let index = self.cursor;
+        let interaction = self
+            .cassette
+            .interactions
+            .get(index)
+            .ok_or_else(|| anyhow!("cassette exhausted at request #{}: {} {}", index + 1, method, url))?;
+
+        let live_url = redact_with(url, &self.secrets);
+
+        let recorded = &interaction.request;
+        if recorded.method != method || recorded.url != live_url {
+            return Err(anyhow!(
+                "cassette mismatch at request #{}: recorded {} {}, got {} {}",
+                index + 1,
+                recorded.method,
+                recorded.url,
+                method,
+                live_url
+            ));
+        }
+
+        self.cursor += 1;
+        Ok(interaction.response.clone())
+    }
+
+    /// Write a recording to disk with credentials and cookies redacted.
+    pub(crate) fn finish(mut self) -> Result<()> {
+        if let CassetteMode::Record(path) = &self.mode {
+            self.cassette.redact(&self.secrets);
+            self.cassette.save(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "p@ss w&rd";
+    const SESSION: &str = "roadclient://s1.ddtank/?user=joe&key=SESSION123";
+
+    fn login(url: &str) -> Interaction {
+        Interaction {
+            request: RecordedRequest {
+                method: "POST".to_owned(),
+                url: url.to_owned(),
+                headers: vec![("Cookie".to_owned(), "sid=COOKIE789".to_owned())],
+                body: Some("username=joe&password=p%40ss+w%26rd".to_owned()),
+                cookies: Some("sid=COOKIE789".to_owned()),
+            },
+            response: RecordedResponse {
+                status: 302,
+                url: SESSION.to_owned(),
+                headers: vec![("Location".to_owned(), SESSION.to_owned())],
+                body: format!(r#"{{"code":0,"token":"TOKEN456","url":"{}"}}"#, SESSION),
+            },
+        }
+    }
+
+    #[test]
+    fn recorded_login_has_no_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("login.json");
+        let url = "https://www.337.com/login?user=joe&pwd2=p@ss%20w%26rd";
+
+        let mut tape = Tape::open(&CassetteMode::Record(path.clone()), &[PASSWORD]).unwrap();
+        tape.record(login(url));
+        tape.finish().unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        for secret in ["p@ss", "p%40ss", "w%26rd", "SESSION123", "TOKEN456", "COOKIE789"] {
+            assert!(!saved.contains(secret), "{} leaked into {}", secret, saved);
+        }
+
+        // A replay with the same password still finds the request
+        let mut tape = Tape::open(&CassetteMode::Replay(path), &[PASSWORD]).unwrap();
+        let response = tape.replay("POST", url).unwrap();
+        assert_eq!(response.status, 302);
+    }
+}
@@ -0,0 +1,523 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use ddtank_rs::{
+    logging::{AccountScope, LogConfig},
+    now_millis,
+    password::{self, PasswordArgs},
+    settings::Overrides,
+    DeletedUser, ExecuteOptions, Location, LoginOutcome, LoginRecord, RetryPolicy, ServerEntry, Settings, StoreEngine, Strategy, UserInfo,
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Headless ddtank-rs launcher over the account database")]
+struct Args {
+    /// Settings file, default to settings.toml in the config directory.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Directory of the account database and logs.
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+
+    /// Keep settings and data next to the executable.
+    #[arg(long, global = true)]
+    portable: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage stored accounts.
+    #[command(subcommand)]
+    Accounts(Box<AccountsCommand>),
+    /// Log in and launch the game for one account, or every account with a tag.
+    Login {
+        /// Account UUID, nickname or username.
+        #[arg(required_unless_present = "tag", conflicts_with = "tag")]
+        account: Option<String>,
+
+        /// Log in every account carrying this tag, one after another.
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Server ID to log into, default to each account's default server.
+        #[arg(short, long)]
+        server: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AccountsCommand {
+    /// List accounts, most recently used first.
+    List {
+        /// Only show accounts carrying this tag.
+        #[arg(short, long)]
+        tag: Option<String>,
+    },
+    /// Add an account.
+    Add {
+        #[arg(short, long)]
+        username: String,
+
+        // Prompted for without echo when no password flag is given.
+        #[command(flatten)]
+        password: PasswordArgs,
+
+        /// Server ID, e.g. 10000.
+        #[arg(short, long)]
+        server: String,
+
+        #[arg(short, long)]
+        nickname: Option<String>,
+
+        /// Default to the default_strategy setting.
+        #[arg(long)]
+        strategy: Option<String>,
+
+        /// Tag to attach, may be repeated.
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+
+        /// How to open the game: auto, system, "flash [path]",
+        /// "roadclient <path>" or "command <template with {url}>".
+        #[arg(long)]
+        launcher: Option<String>,
+    },
+    /// Change fields of an existing account.
+    Edit {
+        /// Account UUID, nickname or username.
+        account: String,
+
+        #[arg(short, long)]
+        username: Option<String>,
+
+        #[command(flatten)]
+        password: PasswordArgs,
+
+        /// New default server, added to the account's servers if needed.
+        #[arg(short, long)]
+        server: Option<String>,
+
+        /// Server to add to the account, may be repeated.
+        #[arg(long = "add-server")]
+        add_servers: Vec<String>,
+
+        /// Server to remove from the account, may be repeated.
+        #[arg(long = "remove-server")]
+        remove_servers: Vec<String>,
+
+        /// Character name on one of the account's servers as ID=NAME, an
+        /// empty NAME clears it. May be repeated.
+        #[arg(long = "server-nickname", value_parser = parse_server_nickname)]
+        server_nicknames: Vec<(String, String)>,
+
+        /// New nickname, an empty string clears it.
+        #[arg(short, long)]
+        nickname: Option<String>,
+
+        #[arg(long)]
+        strategy: Option<String>,
+
+        /// Tag to attach, may be repeated.
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+
+        /// Tag to remove, may be repeated.
+        #[arg(long = "untag")]
+        untags: Vec<String>,
+
+        /// How to open the game, an empty string goes back to the strategy default.
+        #[arg(long)]
+        launcher: Option<String>,
+    },
+    /// Show the last logins of an account, newest first.
+    History {
+        /// Account UUID, nickname or username.
+        account: String,
+    },
+    /// Move an account to the trash.
+    Remove {
+        /// Account UUID, nickname or username.
+        account: String,
+    },
+    /// List the accounts in the trash, most recently deleted first.
+    Trash,
+    /// Bring an account back from the trash.
+    Restore {
+        /// Account UUID, nickname or username.
+        account: String,
+    },
+    /// Erase accounts in the trash for good.
+    Purge {
+        /// Account UUID, nickname or username, every account when omitted.
+        account: Option<String>,
+    },
+    /// List the accounts sharing a username and strategy.
+    Duplicates,
+    /// Fold duplicate accounts into one, moving the others to the trash.
+    Merge {
+        /// Account UUID, nickname or username to keep.
+        keep: String,
+
+        /// Account UUIDs to merge into it.
+        #[arg(required = true)]
+        others: Vec<uuid::Uuid>,
+    },
+    /// Copy the accounts of another userdata.redb, skipping ones already here.
+    Import {
+        path: PathBuf,
+    },
+}
+
+fn open_db(location: &Location, settings: &Settings) -> Result<StoreEngine> {
+    let db_path = location.database();
+    let mut db = StoreEngine::create(db_path.to_str().unwrap()).map_err(|e| {
+        anyhow!(
+            "failed to open {} (is the launcher window open?): {}",
+            db_path.display(),
+            e
+        )
+    })?;
+    db.purge_expired(settings);
+    Ok(db)
+}
+
+/// Look an account in the trash up by UUID, nickname or username.
+fn find_trashed(db: &StoreEngine, key: &str) -> Result<(uuid::Uuid, DeletedUser)> {
+    let matches: Vec<(uuid::Uuid, DeletedUser)> = db
+        .trashed()
+        .into_iter()
+        .filter(|(uuid, deleted)| {
+            uuid.to_string().eq_ignore_ascii_case(key)
+                || deleted.user.nickname.as_deref().is_some_and(|nickname| nickname.eq_ignore_ascii_case(key))
+                || deleted.user.username.eq_ignore_ascii_case(key)
+        })
+        .collect();
+
+    match matches.len() {
+        0 => Err(anyhow!("no account in the trash matches {}", key)),
+        1 => Ok(matches.into_iter().next().unwrap()),
+        n => Err(anyhow!("{} accounts in the trash match {}, use the UUID instead", n, key)),
+    }
+}
+
+fn parse_server_nickname(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(server, name)| (server.to_owned(), name.to_owned()))
+        .ok_or_else(|| format!("expected ID=NAME, got {}", arg))
+}
+
+fn print_account(uuid: &uuid::Uuid, user: &UserInfo) {
+    let tags = if user.tags.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", user.tags.join(", "))
+    };
+    // Servidor padrao primeiro
+    let servers: Vec<&str> = std::iter::once(user.server.as_str())
+        .chain(user.servers.iter().map(|entry| entry.server.as_str()).filter(|server| *server != user.server))
+        .collect();
+    println!(
+        "{}  {:<20} {:<20} {:<8} {}{}",
+        uuid,
+        user.nickname.as_deref().unwrap_or("-"),
+        user.username,
+        servers.join(","),
+        user.strategy,
+        tags
+    );
+}
+
+fn accounts(command: AccountsCommand, location: &Location, settings: &Settings) -> Result<()> {
+    let mut db = open_db(location, settings)?;
+    match command {
+        AccountsCommand::List { tag } => {
+            let mut users = match tag {
+                Some(tag) => db.tagged(&tag),
+                None => db.users(),
+            };
+            users.sort_by_key(|(_, user)| std::cmp::Reverse(user.last_used.unwrap_or(0)));
+            for (uuid, user) in &users {
+                print_account(uuid, user);
+            }
+        }
+        AccountsCommand::Add {
+            username,
+            password,
+            server,
+            nickname,
+            strategy,
+            tags,
+            launcher,
+        } => {
+            let password = match password.read()? {
+                Some(password) => password,
+                None => password::prompt()?,
+            };
+            let strategy = strategy.unwrap_or_else(|| settings.default_strategy.clone());
+            settings.strategy(location).get(&strategy)?;
+            let uuid = uuid::Uuid::new_v4();
+            let user = UserInfo {
+                username,
+                password,
+                strategy,
+                server,
+                servers: Vec::new(),
+                nickname,
+                last_used: None,
+                tags,
+                launcher,
+            };
+            db.insert(&uuid, &user)?;
+            print_account(&uuid, &user);
+        }
+        AccountsCommand::Edit {
+            account,
+            username,
+            password,
+            server,
+            add_servers,
+            remove_servers,
+            server_nicknames,
+            nickname,
+            strategy,
+            tags,
+            untags,
+            launcher,
+        } => {
+            let (uuid, mut user) = db.find(&account)?;
+            if let Some(username) = username {
+                user.username = username;
+            }
+            if let Some(password) = password.read()? {
+                user.password = password;
+            }
+            if let Some(server) = server {
+                user.server = server;
+            }
+            for server in add_servers {
+                user.servers.push(ServerEntry { server, nickname: None, last_used: None });
+            }
+            user.servers.retain(|entry| !remove_servers.contains(&entry.server));
+            if remove_servers.contains(&user.server) {
+                let first = user.servers.first().ok_or_else(|| anyhow!("an account needs at least one server"))?;
+                user.server = first.server.clone();
+            }
+            user.normalize();
+            for (server, name) in server_nicknames {
+                let entry = user
+                    .servers
+                    .iter_mut()
+                    .find(|entry| entry.server == server)
+                    .ok_or_else(|| anyhow!("{} is not one of the account's servers", server))?;
+                entry.nickname = Some(name).filter(|n| !n.is_empty());
+            }
+            if let Some(nickname) = nickname {
+                user.nickname = Some(nickname).filter(|n| !n.is_empty());
+            }
+            if let Some(strategy) = strategy {
+                settings.strategy(location).get(&strategy)?;
+                user.strategy = strategy;
+            }
+            for tag in tags {
+                if !user.tags.contains(&tag) {
+                    user.tags.push(tag);
+                }
+            }
+            user.tags.retain(|tag| !untags.contains(tag));
+            if let Some(launcher) = launcher {
+                user.launcher = Some(launcher).filter(|l| !l.trim().is_empty());
+            }
+            db.insert(&uuid, &user)?;
+            print_account(&uuid, &user);
+        }
+        AccountsCommand::History { account } => {
+            let (uuid, _) = db.find(&account)?;
+            for record in db.history(&uuid) {
+                let minutes = now_millis().saturating_sub(record.at) / (60 * 1000);
+                println!("{:>8} min ago  {:<8} {:<10} {}", minutes, record.server, record.outcome, record.message);
+            }
+        }
+        AccountsCommand::Remove { account } => {
+            let (uuid, user) = db.find(&account)?;
+            db.remove(&uuid)?;
+            println!("moved {} ({}) to the trash", uuid, user.username);
+        }
+        AccountsCommand::Trash => {
+            for (uuid, deleted) in db.trashed() {
+                let days = now_millis().saturating_sub(deleted.deleted_at) / (24 * 60 * 60 * 1000);
+                println!(
+                    "{}  {:<20} {:<20} deleted {} day(s) ago",
+                    uuid,
+                    deleted.user.nickname.as_deref().unwrap_or("-"),
+                    deleted.user.username,
+                    days
+                );
+            }
+        }
+        AccountsCommand::Restore { account } => {
+            let (uuid, _) = find_trashed(&db, &account)?;
+            let user = db.restore(&uuid)?;
+            print_account(&uuid, &user);
+        }
+        AccountsCommand::Purge { account: Some(account) } => {
+            let (uuid, deleted) = find_trashed(&db, &account)?;
+            db.purge(&uuid)?;
+            println!("erased {} ({})", uuid, deleted.user.username);
+        }
+        AccountsCommand::Purge { account: None } => {
+            let count = db.purge_trash(std::time::Duration::ZERO)?;
+            println!("erased {} account(s)", count);
+        }
+        AccountsCommand::Duplicates => {
+            for group in db.duplicates() {
+                for (uuid, user) in &group {
+                    print_account(uuid, user);
+                }
+                println!();
+            }
+        }
+        AccountsCommand::Merge { keep, others } => {
+            let (uuid, _) = db.find(&keep)?;
+            let user = db.merge(&uuid, &others)?;
+            print_account(&uuid, &user);
+            println!("moved {} account(s) to the trash", others.len());
+        }
+        AccountsCommand::Import { path } => {
+            let path = path.to_str().ok_or_else(|| anyhow!("non UTF-8 path {}", path.display()))?;
+            let count = db.import_users(path)?;
+            println!("imported {} account(s)", count);
+        }
+    }
+    Ok(())
+}
+
+/// Log one account in on `server`, its default server when empty, in the
+/// same way the launcher window does.
+fn login_one(
+    db: &mut StoreEngine,
+    strategy: &Strategy,
+    settings: &Settings,
+    uuid: &uuid::Uuid,
+    user: UserInfo,
+    server: &str,
+) -> Result<()> {
+    let _scope = AccountScope::enter(&user.username);
+    let server = user
+        .server_entry(server)
+        .map(|entry| entry.server.clone())
+        .ok_or_else(|| anyhow!("the account has no server {}", server))?;
+    let script = strategy.get(&user.strategy)?;
+
+    let timeout = ddtank_rs::login_timeout(&script, settings.login_timeout());
+    let options = ExecuteOptions {
+        timeout: Some(timeout),
+        user_agent: Some(settings.user_agent.clone()),
+        ..ExecuteOptions::default()
+    };
+    let policy = RetryPolicy::for_script(&script);
+    let outcome = ddtank_rs::execute_login_with_retry(
+        &script,
+        &user.username,
+        &user.password,
+        &server,
+        &options,
+        &policy,
+        |retry| {
+            println!(
+                "attempt {}/{} failed, retrying in {}s",
+                retry.attempt,
+                retry.max_attempts,
+                retry.delay.as_secs_f32()
+            )
+        },
+    );
+    db.record_login(uuid, LoginRecord::new(&server, &outcome))?;
+    let result = match outcome {
+        LoginOutcome::Success(result) => result,
+        LoginOutcome::Failed { message, .. } => return Err(anyhow!(message)),
+        LoginOutcome::TimedOut => return Err(anyhow!("login timed out after {}s", timeout.as_secs())),
+        LoginOutcome::Cancelled => return Err(anyhow!("login cancelled")),
+    };
+    if ddtank_rs::is_game_url(&result) {
+        let launcher = ddtank_rs::launcher_spec(&user, &script);
+        let game = ddtank_rs::launch_game(&result, launcher.as_deref(), settings)?;
+        if game.is_tracked() {
+            println!("started {} (pid {})", game.launcher, game.id());
+        } else {
+            println!("opened with the {} launcher", game.launcher);
+        }
+    } else {
+        println!("{}", result);
+    }
+    Ok(())
+}
+
+fn login(
+    account: Option<String>,
+    tag: Option<String>,
+    server: Option<String>,
+    location: &Location,
+    settings: &Settings,
+) -> Result<()> {
+    let mut db = open_db(location, settings)?;
+    let strategy = settings.strategy(location);
+
+    let targets = match (account, tag) {
+        (Some(account), _) => vec![db.find(&account)?],
+        (None, Some(tag)) => db.tagged(&tag),
+        (None, None) => Vec::new(),
+    };
+    if targets.is_empty() {
+        return Err(anyhow!("no accounts to log in"));
+    }
+
+    let mut failures = 0;
+    for (uuid, user) in targets {
+        let name = user.nickname.clone().unwrap_or_else(|| user.username.clone());
+        println!("==> {}", name);
+        if let Err(err) = login_one(&mut db, &strategy, settings, &uuid, user, server.as_deref().unwrap_or_default()) {
+            log::error!("{}: {:#}", name, err);
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        return Err(anyhow!("{} login(s) failed", failures));
+    }
+    Ok(())
+}
+
+// ddtank-rs headless launcher.
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let location = Location::resolve(&Overrides {
+        config: args.config,
+        data_dir: args.data_dir,
+        portable: args.portable,
+    });
+    if let Err(e) = location.ensure_data_dir() {
+        eprintln!("warning: {:#}", e);
+    }
+    ddtank_rs::logging::init(LogConfig {
+        dir: Some(location.logs_dir()),
+        ..LogConfig::default()
+    });
+    let settings = Settings::effective(&location);
+    let result = match args.command {
+        Command::Accounts(command) => accounts(*command, &location, &settings),
+        Command::Login { account, tag, server } => login(account, tag, server, &location, &settings),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", ddtank_rs::redact::redact(&format!("{:#}", err)));
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,27 +1,157 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
 
-use clap::Parser;
+use anyhow::{anyhow, Context, Result};
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use ddtank_rs::{
+    logging::LogConfig,
+    password::{self, PasswordArgs},
+    redact, settings::Overrides, CassetteMode, ExecuteOptions, Location, LoginOutcome, Settings,
+    StrategyMetadata,
+};
+use serde_json::json;
 
-#[derive(Parser, Debug, Default)]
-#[command(version)]
+#[derive(Parser, Debug)]
+#[command(version, about = "ddtank-rs strategy development tool")]
 struct Args {
-    /// Lua script name(path), required!
+    /// Print results as JSON.
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Trace every HTTP request made by the strategy to stderr.
+    /// Same as DDTANK_LOG=info,http=debug.
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a strategy's login against the live server.
+    Run {
+        /// Lua script name(path), required!
+        #[arg(short, long)]
+        name: PathBuf,
+
+        #[command(flatten)]
+        credentials: Credentials,
+
+        /// Record every HTTP request/response to this cassette file (credentials redacted).
+        #[arg(long, conflicts_with = "replay")]
+        record: Option<PathBuf>,
+
+        /// Replay HTTP traffic from this cassette file instead of hitting the server.
+        #[arg(long)]
+        replay: Option<PathBuf>,
+
+        /// Give up after this many seconds, default to the script's `-- Timeout:`
+        /// metadata, else the launcher's login_timeout setting.
+        #[arg(short, long)]
+        timeout: Option<u64>,
+    },
+    /// Check a script's syntax and that it defines `login`.
+    Check {
+        /// Lua script path.
+        script: PathBuf,
+    },
+    /// List strategies in a directory with their metadata.
+    List {
+        /// Directory to scan for `*.lua` files.
+        #[arg(default_value = "scripts")]
+        dir: PathBuf,
+    },
+    /// Print the server list returned by a strategy's `servers()`.
+    Servers {
+        /// Lua script path.
+        script: PathBuf,
+    },
+    /// Run a strategy offline against a recorded cassette.
+    Test {
+        /// Lua script path.
+        script: PathBuf,
+
+        /// Cassette recorded with `run --record`.
+        #[arg(short, long)]
+        cassette: PathBuf,
+
+        #[command(flatten)]
+        credentials: Credentials,
+    },
+}
+
+#[derive(ClapArgs, Debug)]
+struct Credentials {
+    /// Stored account (UUID, nickname or username) to take username, password and server from.
     #[arg(short, long)]
-    name: PathBuf,
+    account: Option<String>,
+
+    /// Account database used by --account, default to the launcher's userdata.redb.
+    #[arg(long, requires = "account")]
+    db: Option<PathBuf>,
 
     /// Username, default to environment variable DDTANK_USERNAME or "".
     #[arg(short, long)]
     username: Option<String>,
 
-    /// Password, default to environment variable DDTANK_PASSWORD or "".
-    #[arg(short, long)]
-    password: Option<String>,
+    // Default to --account, then to environment variable DDTANK_PASSWORD,
+    // then to a prompt when stdin is a terminal, except for replays.
+    #[command(flatten)]
+    password: PasswordArgs,
 
     /// Server ID, default to environment variable DDTANK_SERVER_ID or "".
     #[arg(short, long)]
     server_id: Option<String>,
 }
 
+impl Credentials {
+    /// Resolve `(username, password, server)` from flags, the account
+    /// database, a password source or the environment, in that order. Without
+    /// `prompt` a missing password is "" instead of asked for.
+    fn resolve(self, location: &Location, prompt: bool) -> Result<(String, String, String)> {
+        let stored = match &self.account {
+            Some(account) => {
+                let db_path = self.db.clone().unwrap_or_else(|| location.database());
+                let db = ddtank_rs::StoreEngine::open(&db_path)
+                    .with_context(|| format!("failed to open {} (is the launcher window open?)", db_path.display()))?;
+                Some(db.find(account)?.1)
+            }
+            None => None,
+        };
+
+        let password = match self.password.read()? {
+            Some(password) => password,
+            None => match &stored {
+                Some(user) => user.password.clone(),
+                None => match std::env::var("DDTANK_PASSWORD") {
+                    Ok(password) => password,
+                    Err(_) if prompt && std::io::stdin().is_terminal() => password::prompt()?,
+                    Err(_) => "".to_owned(),
+                },
+            },
+        };
+
+        let (username, server) = match stored {
+            Some(user) => (
+                self.username.unwrap_or(user.username),
+                self.server_id.unwrap_or(user.server),
+            ),
+            None => (
+                self.username.unwrap_or_load_from_env("DDTANK_USERNAME"),
+                self.server_id.unwrap_or_load_from_env("DDTANK_SERVER_ID"),
+            ),
+        };
+
+        Ok((username, password, server))
+    }
+}
+
 trait LoadFromEnv {
     fn unwrap_or_load_from_env(self, env_name: &str) -> String;
 }
@@ -37,18 +167,168 @@ impl LoadFromEnv for Option<String> {
     }
 }
 
-// ddtank-rs lua test interface.
-fn main() {
-    let args = Args::parse();
-    let script_path = args.name;
+/// What a subcommand prints, in both output formats.
+struct Report {
+    json: serde_json::Value,
+    text: String,
+}
 
-    let username = args.username.unwrap_or_load_from_env("DDTANK_USERNAME");
-    let password = args.password.unwrap_or_load_from_env("DDTANK_PASSWORD");
-    let server = args.server_id.unwrap_or_load_from_env("DDTANK_SERVER_ID");
+fn read_script(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("failed to read script {}", path.display()))
+}
+
+fn login(
+    script_path: &Path,
+    credentials: Credentials,
+    cassette: Option<CassetteMode>,
+    timeout: Option<u64>,
+) -> Result<Report> {
+    let location = Location::resolve(&Overrides::default());
+    let settings = Settings::effective(&location);
+    // A replay matches redacted URLs, so it never needs the real password.
+    let replay = matches!(cassette, Some(CassetteMode::Replay(_)));
+    let (username, password, server) = credentials.resolve(&location, !replay)?;
+    let secrets = [password.clone()];
+
+    let script = read_script(script_path)?;
+    let timeout = timeout.map_or_else(
+        || ddtank_rs::login_timeout(&script, settings.login_timeout()),
+        Duration::from_secs,
+    );
+    let options = ExecuteOptions {
+        cassette,
+        timeout: Some(timeout),
+        user_agent: Some(settings.user_agent.clone()),
+        ..ExecuteOptions::default()
+    };
+    let result = match ddtank_rs::execute_login(&script, &username, &password, &server, &options) {
+        LoginOutcome::Success(result) => result,
+        LoginOutcome::Failed { message, .. } => {
+            return Err(anyhow!(redact::redact_secrets(&message, &secrets)))
+        }
+        LoginOutcome::TimedOut => return Err(anyhow!("login timed out after {}s", timeout.as_secs())),
+        LoginOutcome::Cancelled => return Err(anyhow!("login cancelled")),
+    };
+    let result = redact::redact_secrets(&result, &secrets);
+
+    Ok(Report {
+        json: json!({ "script": script_path, "result": result }),
+        text: format!("script {:?} runs with result:\n{}", script_path, result),
+    })
+}
+
+fn check(script_path: &Path) -> Result<Report> {
+    let script = read_script(script_path)?;
+    let check = ddtank_rs::check_strategy(&script)?;
+    let servers = if check.has_servers { "yes" } else { "no" };
+    Ok(Report {
+        json: json!({ "script": script_path, "has_servers": check.has_servers }),
+        text: format!("{}: ok (servers(): {})", script_path.display(), servers),
+    })
+}
+
+fn list(dir: &Path) -> Result<Report> {
+    let pattern = dir.join("*.lua");
+    let pattern = pattern.to_str().ok_or_else(|| anyhow!("non UTF-8 path {}", dir.display()))?;
+
+    let mut entries = Vec::new();
+    let mut text = Vec::new();
+    for path in glob::glob(pattern)?.flatten() {
+        let script = read_script(&path)?;
+        let metadata = StrategyMetadata::parse(&script);
+        let error = ddtank_rs::check_strategy(&script).err().map(|e| format!("{:#}", e));
+        let file = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
 
-    let script = fs::read_to_string(&script_path).unwrap();
-    let result = ddtank_rs::execute_strategy(&script, &username, &password, &server).unwrap();
+        text.push(format!(
+            "{:<16} {:<28} {}{}",
+            file,
+            metadata.get("strategy").unwrap_or("-"),
+            metadata.get("comment").unwrap_or(""),
+            error.as_ref().map(|e| format!(" [invalid: {}]", e)).unwrap_or_default()
+        ));
+        entries.push(json!({
+            "file": file,
+            "metadata": metadata.fields,
+            "valid": error.is_none(),
+            "error": error,
+        }));
+    }
+
+    Ok(Report {
+        json: json!(entries),
+        text: text.join("\n"),
+    })
+}
 
-    println!("script {:?} runs with result:", &script_path);
-    println!("{}", result);
+fn servers(script_path: &Path) -> Result<Report> {
+    let script = read_script(script_path)?;
+    let servers = ddtank_rs::strategy_servers(&script)?;
+    let text = servers
+        .iter()
+        .map(|s| format!("{:<8} {} ({})", s.id, s.name, s.range))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(Report {
+        json: serde_json::to_value(&servers)?,
+        text,
+    })
+}
+
+fn run(args: Args) -> Result<Report> {
+    match args.command {
+        Command::Run {
+            name,
+            credentials,
+            record,
+            replay,
+            timeout,
+        } => {
+            let cassette = match (record, replay) {
+                (Some(path), _) => Some(CassetteMode::Record(path)),
+                (_, Some(path)) => Some(CassetteMode::Replay(path)),
+                _ => None,
+            };
+            login(&name, credentials, cassette, timeout)
+        }
+        Command::Check { script } => check(&script),
+        Command::List { dir } => list(&dir),
+        Command::Servers { script } => servers(&script),
+        Command::Test {
+            script,
+            cassette,
+            credentials,
+        } => {
+            login(&script, credentials, Some(CassetteMode::Replay(cassette)), None)
+        }
+    }
+}
+
+// ddtank-rs lua test interface.
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let filter = if args.verbose { "info,http=debug" } else { "info" };
+    ddtank_rs::logging::init(LogConfig {
+        filter: filter.to_owned(),
+        ..LogConfig::default()
+    });
+    let json = args.json;
+
+    match run(args) {
+        Ok(report) => {
+            if json {
+                println!("{}", json!({ "ok": true, "data": report.json }));
+            } else {
+                println!("{}", report.text);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            if json {
+                println!("{}", json!({ "ok": false, "error": format!("{:#}", err) }));
+            } else {
+                eprintln!("error: {:#}", err);
+            }
+            ExitCode::FAILURE
+        }
+    }
 }
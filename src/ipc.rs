@@ -0,0 +1,424 @@
+//! Data exchanged with the UI. Values are built from the serde form of the
+//! library types and [`shapes`] traces those same types, so a new field of
+//! [`UserInfo`], [`ToolInfo`] or [`Settings`] reaches the UI and the
+//! generated `src/ui/js/shapes.js` without being listed anywhere.
+
+use anyhow::{anyhow, Result};
+use serde::de::{self, IntoDeserializer, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::logging::LogEntry;
+use crate::{DeletedUser, Location, ServerEntry, Settings, ToolInfo, UserInfo};
+
+/// Account fields the UI never gets.
+const ACCOUNT_HIDDEN: &[&str] = &["password"];
+
+/// Settings fields the UI never gets, the master password hash.
+const SETTINGS_HIDDEN: &[&str] = &["master_password"];
+
+#[derive(Serialize, Deserialize)]
+struct AccountFields {
+    has_password: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntryFields {
+    id: String,
+    has_password: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeletedFields {
+    id: String,
+    deleted_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ToolFields {
+    id: String,
+    running: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SettingsFields {
+    /// Whether a master password is set.
+    master_password: bool,
+    portable: bool,
+    data_dir: String,
+    settings_file: String,
+}
+
+/// A running game client.
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub pid: u32,
+    /// Milliseconds since the Unix epoch.
+    pub started_at: u64,
+    pub launcher: String,
+}
+
+/// An account as the UI sees it: everything but the password.
+pub fn account(user: &UserInfo) -> Result<Value> {
+    view(user, ACCOUNT_HIDDEN, &AccountFields {
+        has_password: !user.password.is_empty(),
+    })
+}
+
+/// An account with its ID, for lists that aren't keyed by it.
+pub fn account_entry(uuid: &uuid::Uuid, user: &UserInfo) -> Result<Value> {
+    view(user, ACCOUNT_HIDDEN, &EntryFields {
+        id: uuid.to_string(),
+        has_password: !user.password.is_empty(),
+    })
+}
+
+/// An account in the trash, with its ID and without its password.
+pub fn deleted_account(uuid: &uuid::Uuid, deleted: &DeletedUser) -> Result<Value> {
+    view(&deleted.user, ACCOUNT_HIDDEN, &DeletedFields {
+        id: uuid.to_string(),
+        deleted_at: deleted.deleted_at,
+    })
+}
+
+pub fn tool(uuid: &uuid::Uuid, tool: &ToolInfo, running: bool) -> Result<Value> {
+    view(tool, &[], &ToolFields {
+        id: uuid.to_string(),
+        running,
+    })
+}
+
+/// Settings plus where they and the data live. Only says whether a master
+/// password is set.
+pub fn settings(settings: &Settings, location: &Location) -> Result<Value> {
+    view(settings, SETTINGS_HIDDEN, &SettingsFields {
+        master_password: settings.master_password.is_some(),
+        portable: location.portable,
+        data_dir: location.data_dir.display().to_string(),
+        settings_file: location.settings_file().display().to_string(),
+    })
+}
+
+pub fn logs(entries: &[LogEntry]) -> Result<Value> {
+    Ok(serde_json::to_value(entries)?)
+}
+
+pub fn game(game: &Game) -> Result<Value> {
+    Ok(serde_json::to_value(game)?)
+}
+
+/// `data` without its `hidden` fields, plus the fields of `extra`.
+fn view<T: Serialize, E: Serialize>(data: &T, hidden: &[&str], extra: &E) -> Result<Value> {
+    let mut object = into_object(serde_json::to_value(data)?)?;
+    for field in hidden {
+        object.remove(*field);
+    }
+    object.extend(into_object(serde_json::to_value(extra)?)?);
+    Ok(Value::Object(object))
+}
+
+fn into_object(value: Value) -> Result<Map<String, Value>> {
+    match value {
+        Value::Object(object) => Ok(object),
+        other => Err(anyhow!("expected an object, got {}", other)),
+    }
+}
+
+// ===== Shapes =====
+
+/// The type of a value as JavaScript sees it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// A unit, or a type that was never visited.
+    Unknown,
+    Boolean,
+    Number,
+    String,
+    /// May be missing or null.
+    Optional(Box<Shape>),
+    Array(Box<Shape>),
+    /// An object keyed by strings.
+    Map(Box<Shape>),
+    Object(Vec<(String, Shape)>),
+}
+
+impl Shape {
+    /// Trace the shape `T` deserializes from.
+    pub fn of<T: for<'de> Deserialize<'de>>() -> Shape {
+        let mut shape = Shape::Unknown;
+        if let Err(e) = T::deserialize(Tracer(&mut shape)) {
+            panic!("can't trace {}: {}", std::any::type_name::<T>(), e);
+        }
+        shape
+    }
+
+    fn without(mut self, hidden: &[&str]) -> Shape {
+        if let Shape::Object(fields) = &mut self {
+            fields.retain(|(name, _)| !hidden.contains(&name.as_str()));
+        }
+        self
+    }
+
+    fn with(mut self, other: Shape) -> Shape {
+        if let (Shape::Object(fields), Shape::Object(extra)) = (&mut self, other) {
+            fields.extend(extra);
+        }
+        self
+    }
+
+    /// The JSDoc type, without optionality.
+    fn jsdoc(&self) -> String {
+        match self {
+            Shape::Unknown => "*".to_owned(),
+            Shape::Boolean => "boolean".to_owned(),
+            Shape::Number => "number".to_owned(),
+            Shape::String => "string".to_owned(),
+            Shape::Optional(inner) => inner.jsdoc(),
+            Shape::Array(inner) => format!("{}[]", inner.jsdoc()),
+            Shape::Map(inner) => format!("Object<string, {}>", inner.jsdoc()),
+            Shape::Object(_) => "Object".to_owned(),
+        }
+    }
+}
+
+/// Every shape the UI gets or sends, by name. `*Form` shapes are what the
+/// UI sends to save.
+pub fn shapes() -> Vec<(&'static str, Shape)> {
+    let account = || Shape::of::<UserInfo>().without(ACCOUNT_HIDDEN);
+    vec![
+        ("Account", account().with(Shape::of::<AccountFields>())),
+        ("AccountForm", Shape::of::<UserInfo>()),
+        ("AccountEntry", account().with(Shape::of::<EntryFields>())),
+        ("ServerEntry", Shape::of::<ServerEntry>()),
+        ("DeletedAccount", account().with(Shape::of::<DeletedFields>())),
+        ("Tool", Shape::of::<ToolInfo>().with(Shape::of::<ToolFields>())),
+        ("ToolForm", Shape::of::<ToolInfo>()),
+        (
+            "Settings",
+            Shape::of::<Settings>()
+                .without(SETTINGS_HIDDEN)
+                .with(Shape::of::<SettingsFields>()),
+        ),
+        ("SettingsForm", Shape::of::<Settings>().without(SETTINGS_HIDDEN)),
+        ("Game", Shape::of::<Game>()),
+        ("LogEntry", Shape::of::<LogEntry>()),
+    ]
+}
+
+/// The contents of `src/ui/js/shapes.js`: a JSDoc typedef and a field list
+/// per shape.
+pub fn shapes_js() -> String {
+    let shapes = shapes();
+    let mut js = String::from(
+        "// Gerado a partir de src/ipc.rs. Nao edite: rode `UPDATE_SHAPES=1 cargo test --test ipc`.\n\
+         // Campos com `?` podem faltar; listas vazias tambem.\n",
+    );
+
+    for (name, shape) in &shapes {
+        js.push_str(&format!("\n/**\n * @typedef {{Object}} {}\n", name));
+        for (field, shape) in fields(shape) {
+            let field = match shape {
+                Shape::Optional(_) => format!("[{}]", field),
+                _ => field.to_owned(),
+            };
+            js.push_str(&format!(" * @property {{{}}} {}\n", shape.jsdoc(), field));
+        }
+        js.push_str(" */\n");
+    }
+
+    js.push_str("\nexport const SHAPES = {\n");
+    let objects: Vec<String> = shapes
+        .iter()
+        .map(|(name, shape)| {
+            let fields: Vec<String> = fields(shape)
+                .iter()
+                .map(|(field, shape)| {
+                    let optional = if matches!(shape, Shape::Optional(_)) { "?" } else { "" };
+                    format!("        {}: \"{}{}\"", field, shape.jsdoc(), optional)
+                })
+                .collect();
+            format!("    {}: {{\n{}\n    }}", name, fields.join(",\n"))
+        })
+        .collect();
+    js.push_str(&objects.join(",\n"));
+    js.push_str("\n};\n");
+    js
+}
+
+fn fields(shape: &Shape) -> &[(String, Shape)] {
+    match shape {
+        Shape::Object(fields) => fields,
+        _ => &[],
+    }
+}
+
+/// A deserializer that hands every visitor a placeholder value and writes
+/// down what was asked for.
+struct Tracer<'a>(&'a mut Shape);
+
+macro_rules! trace_as {
+    ($($method:ident => $shape:expr, $visit:ident($($value:expr)?);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                *self.0 = $shape;
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Tracer<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("self-describing types can't be traced"))
+    }
+
+    trace_as! {
+        deserialize_bool => Shape::Boolean, visit_bool(false);
+        deserialize_i8 => Shape::Number, visit_i8(0);
+        deserialize_i16 => Shape::Number, visit_i16(0);
+        deserialize_i32 => Shape::Number, visit_i32(0);
+        deserialize_i64 => Shape::Number, visit_i64(0);
+        deserialize_u8 => Shape::Number, visit_u8(0);
+        deserialize_u16 => Shape::Number, visit_u16(0);
+        deserialize_u32 => Shape::Number, visit_u32(0);
+        deserialize_u64 => Shape::Number, visit_u64(0);
+        deserialize_f32 => Shape::Number, visit_f32(0.0);
+        deserialize_f64 => Shape::Number, visit_f64(0.0);
+        deserialize_char => Shape::String, visit_char(' ');
+        deserialize_str => Shape::String, visit_str("");
+        deserialize_string => Shape::String, visit_str("");
+        deserialize_identifier => Shape::String, visit_str("");
+        deserialize_bytes => Shape::Array(Box::new(Shape::Number)), visit_bytes(&[]);
+        deserialize_byte_buf => Shape::Array(Box::new(Shape::Number)), visit_bytes(&[]);
+        deserialize_unit => Shape::Unknown, visit_unit();
+        deserialize_ignored_any => Shape::Unknown, visit_unit();
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut inner = Shape::Unknown;
+        let value = visitor.visit_some(Tracer(&mut inner))?;
+        *self.0 = Shape::Optional(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(1, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut inner = Shape::Unknown;
+        let value = visitor.visit_seq(Elements { shape: &mut inner, left: len })?;
+        *self.0 = Shape::Array(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut inner = Shape::Unknown;
+        let value = visitor.visit_map(Entry { shape: &mut inner, left: true })?;
+        *self.0 = Shape::Map(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let mut traced = Vec::new();
+        let value = visitor.visit_map(Fields { names: fields, traced: &mut traced })?;
+        *self.0 = Shape::Object(traced);
+        Ok(value)
+    }
+
+    /// Only enums of unit variants, which serde writes as their name.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = Shape::String;
+        let variant = variants.first().ok_or_else(|| de::Error::custom("enum without variants"))?;
+        visitor.visit_enum(variant.into_deserializer())
+    }
+}
+
+/// `left` elements, all traced into the same shape.
+struct Elements<'a> {
+    shape: &'a mut Shape,
+    left: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for Elements<'_> {
+    type Error = de::value::Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        if self.left == 0 {
+            return Ok(None);
+        }
+        self.left -= 1;
+        seed.deserialize(Tracer(self.shape)).map(Some)
+    }
+}
+
+/// A single entry with an empty string key.
+struct Entry<'a> {
+    shape: &'a mut Shape,
+    left: bool,
+}
+
+impl<'de> de::MapAccess<'de> for Entry<'_> {
+    type Error = de::value::Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        if !std::mem::take(&mut self.left) {
+            return Ok(None);
+        }
+        seed.deserialize("".into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, Self::Error> {
+        seed.deserialize(Tracer(self.shape))
+    }
+}
+
+/// Every field of a struct in declaration order.
+struct Fields<'a> {
+    names: &'static [&'static str],
+    traced: &'a mut Vec<(String, Shape)>,
+}
+
+impl<'de> de::MapAccess<'de> for Fields<'_> {
+    type Error = de::value::Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        let Some(name) = self.names.get(self.traced.len()) else { return Ok(None) };
+        self.traced.push((name.to_string(), Shape::Unknown));
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, Self::Error> {
+        let (_, shape) = self.traced.last_mut().ok_or_else(|| de::Error::custom("value before key"))?;
+        seed.deserialize(Tracer(shape))
+    }
+}
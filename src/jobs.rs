@@ -0,0 +1,59 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+
+/// Worker count used when [`crate::Settings::workers`] is not set.
+pub const DEFAULT_WORKERS: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A fixed number of threads running queued jobs in order. Jobs beyond the
+/// worker count wait in the queue instead of spawning more threads, so a job
+/// with a deadline should measure it from when it was queued, not from when
+/// it starts.
+///
+/// Dropping the pool closes the queue; workers exit once the jobs already
+/// queued are done. They are not joined, so a stuck job cannot hold up
+/// shutdown.
+pub struct WorkerPool {
+    sender: mpsc::Sender<Job>,
+}
+
+impl WorkerPool {
+    pub fn new(size: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for id in 0..size.max(1) {
+            let receiver = receiver.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("worker-{}", id))
+                .spawn(move || loop {
+                    // The lock is released before the job runs.
+                    let job = match receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => return,
+                    };
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => return,
+                    }
+                });
+            if let Err(e) = spawned {
+                log::error!("failed to spawn worker {}: {}", id, e);
+            }
+        }
+
+        Self { sender }
+    }
+
+    /// Queue `job` to run on the next free worker.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, job: F) -> Result<()> {
+        self.sender
+            .send(Box::new(job))
+            .map_err(|_| anyhow!("no worker left to run the job"))
+    }
+}
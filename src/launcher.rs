@@ -0,0 +1,324 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::now_millis;
+
+/// How often [`GameProcess::watch`] checks whether the process exited.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Starts the game for a URL returned by a strategy.
+///
+/// A launcher is chosen from a spec string, stored per account or given by a
+/// strategy's `-- Launcher:` metadata:
+///
+/// | spec                        | launcher                                      |
+/// |-----------------------------|-----------------------------------------------|
+/// | `auto` (or nothing)         | Flash projector for `.swf`, system otherwise  |
+/// | `system`                    | [`SystemLauncher`]                            |
+/// | `flash [path]`              | [`FlashProjector`]                            |
+/// | `roadclient <path>`         | [`RoadclientHandler`]                         |
+/// | `command <template>`        | [`CommandTemplate`], `{url}` is substituted   |
+pub trait GameLauncher: Send + Sync {
+    /// Short name used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Spawn the game without waiting for it.
+    fn launch(&self, url: &str) -> Result<GameProcess>;
+}
+
+/// A spawned game client, or the opener that handed the URL to one.
+/// Clones share the same process.
+#[derive(Clone)]
+pub struct GameProcess {
+    pub launcher: &'static str,
+    /// Whether `pid` is the game itself, see [`GameProcess::is_tracked`].
+    tracked: bool,
+    pid: u32,
+    /// Milliseconds since the Unix epoch.
+    started_at: u64,
+    child: Arc<Mutex<Child>>,
+}
+
+impl GameProcess {
+    fn spawn(launcher: &'static str, command: &mut Command) -> Result<Self> {
+        Self::spawn_with(launcher, command, true)
+    }
+
+    fn spawn_with(launcher: &'static str, command: &mut Command, tracked: bool) -> Result<Self> {
+        let child = command
+            .spawn()
+            .with_context(|| format!("failed to start {:?}", command.get_program()))?;
+        log::info!("{}: processo {} iniciado", launcher, child.id());
+        Ok(Self {
+            launcher,
+            tracked,
+            pid: child.id(),
+            started_at: now_millis(),
+            child: Arc::new(Mutex::new(child)),
+        })
+    }
+
+    /// Whether the process is the game client. An untracked one only
+    /// handed the URL to whatever the system opens it with, so its exit,
+    /// window and kill say nothing about the game.
+    pub fn is_tracked(&self) -> bool {
+        self.tracked
+    }
+
+    pub fn id(&self) -> u32 {
+        self.pid
+    }
+
+    pub fn started_at(&self) -> u64 {
+        self.started_at
+    }
+
+    /// `Some(code)` once the process has exited, see [`GameProcess::watch`].
+    pub fn exit_code(&self) -> Option<Option<i32>> {
+        let mut child = self.child.lock().ok()?;
+        match child.try_wait() {
+            Ok(Some(status)) => Some(status.code()),
+            Ok(None) => None,
+            Err(_) => Some(None),
+        }
+    }
+
+    /// Poll the process on its own thread and report its exit code once,
+    /// `None` when it was killed by a signal or could not be waited on.
+    pub fn watch<F: FnOnce(Option<i32>) + Send + 'static>(&self, on_exit: F) {
+        let process = self.clone();
+        std::thread::spawn(move || {
+            let code = loop {
+                if let Some(code) = process.exit_code() {
+                    break code;
+                }
+                std::thread::sleep(WATCH_INTERVAL);
+            };
+            log::info!("{}: processo {} encerrado com código {:?}", process.launcher, process.pid, code);
+            on_exit(code);
+        });
+    }
+
+    /// Kill the process and everything it started, so a game opened through
+    /// `start /wait` or a wrapper such as `wine` goes down with it.
+    pub fn kill(&self) -> Result<()> {
+        if cfg!(target_os = "windows") {
+            let status = Command::new("taskkill")
+                .args(["/PID", &self.pid.to_string(), "/T", "/F"])
+                .status()?;
+            if status.success() {
+                return Ok(());
+            }
+        }
+        let mut child = self.child.lock().map_err(|_| anyhow!("process lock poisoned"))?;
+        child.kill()?;
+        Ok(())
+    }
+
+    /// Bring the game window, the process' or one of its children's, to the
+    /// front. Needs PowerShell on Windows and `xdotool` elsewhere.
+    pub fn focus(&self) -> Result<()> {
+        let pid = self.pid;
+        let status = if cfg!(target_os = "windows") {
+            let script = format!(
+                "$ids = @({pid}) + @((Get-CimInstance Win32_Process -Filter 'ParentProcessId={pid}').ProcessId); \
+                 $shell = New-Object -ComObject WScript.Shell; \
+                 foreach ($id in $ids) {{ if ($shell.AppActivate([int]$id)) {{ exit 0 }} }}; exit 1"
+            );
+            Command::new("powershell")
+                .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+                .status()
+        } else {
+            Command::new("xdotool")
+                .args(["search", "--pid", &pid.to_string(), "windowactivate"])
+                .status()
+        }
+        .context("failed to run the window focus helper")?;
+
+        if !status.success() {
+            return Err(anyhow!("no window found for process {}", pid));
+        }
+        Ok(())
+    }
+}
+
+/// Hands the URL to the operating system: `start /wait` on Windows,
+/// `open -W` on macOS and `xdg-open` elsewhere. Works for both web pages and
+/// registered protocols like `roadclient://`. The first two wait for the
+/// client, so the game is tracked through them; `xdg-open` may return right
+/// away, so its process is untracked.
+pub struct SystemLauncher;
+
+impl GameLauncher for SystemLauncher {
+    fn name(&self) -> &'static str {
+        "system"
+    }
+
+    fn launch(&self, url: &str) -> Result<GameProcess> {
+        if cfg!(target_os = "windows") {
+            // /wait keeps cmd alive as long as the client, so it can be tracked
+            GameProcess::spawn(self.name(), Command::new("cmd").args(["/c", "start", "/wait", "", url]))
+        } else if cfg!(target_os = "macos") {
+            // -W waits until the application that took the URL quits
+            GameProcess::spawn(self.name(), Command::new("open").args(["-W", url]))
+        } else {
+            GameProcess::spawn_with(self.name(), Command::new("xdg-open").arg(url), false)
+        }
+    }
+}
+
+/// A standalone Flash Player projector, by default `flashplayer_sa.exe`
+/// (or `flashplayer`) next to the executable.
+pub struct FlashProjector {
+    pub path: PathBuf,
+}
+
+impl Default for FlashProjector {
+    fn default() -> Self {
+        let name = if cfg!(target_os = "windows") {
+            "flashplayer_sa.exe"
+        } else {
+            "flashplayer"
+        };
+        Self {
+            path: crate::exe_dir().join(name),
+        }
+    }
+}
+
+impl GameLauncher for FlashProjector {
+    fn name(&self) -> &'static str {
+        "flash"
+    }
+
+    fn launch(&self, url: &str) -> Result<GameProcess> {
+        GameProcess::spawn(self.name(), Command::new(&self.path).arg(url))
+    }
+}
+
+/// A game client that takes `roadclient://` URLs as its argument, for
+/// systems where the protocol is not registered.
+pub struct RoadclientHandler {
+    pub path: PathBuf,
+}
+
+impl GameLauncher for RoadclientHandler {
+    fn name(&self) -> &'static str {
+        "roadclient"
+    }
+
+    fn launch(&self, url: &str) -> Result<GameProcess> {
+        if !url.starts_with("roadclient://") {
+            return Err(anyhow!("roadclient launcher only opens roadclient:// URLs"));
+        }
+        GameProcess::spawn(self.name(), Command::new(&self.path).arg(url))
+    }
+}
+
+/// An arbitrary command line such as `wine "C:/DDTank/client.exe" {url}`.
+/// Arguments are split on whitespace, double quotes group them and every
+/// `{url}` is replaced with the game URL; without one the URL is appended.
+pub struct CommandTemplate {
+    pub template: String,
+}
+
+impl GameLauncher for CommandTemplate {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    fn launch(&self, url: &str) -> Result<GameProcess> {
+        let mut args = split_args(&self.template);
+        if !args.iter().any(|arg| arg.contains("{url}")) {
+            args.push("{url}".to_owned());
+        }
+        let mut args = args.into_iter().map(|arg| arg.replace("{url}", url));
+        let program = args.next().ok_or_else(|| anyhow!("empty launcher command"))?;
+        GameProcess::spawn(self.name(), Command::new(program).args(args))
+    }
+}
+
+/// The Flash projector for `.swf` URLs, the system handler for anything else.
+#[derive(Default)]
+pub struct AutoLauncher {
+    pub flash: FlashProjector,
+}
+
+impl GameLauncher for AutoLauncher {
+    fn name(&self) -> &'static str {
+        "auto"
+    }
+
+    fn launch(&self, url: &str) -> Result<GameProcess> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        if path.to_ascii_lowercase().ends_with(".swf") {
+            self.flash.launch(url)
+        } else {
+            SystemLauncher.launch(url)
+        }
+    }
+}
+
+/// Build the launcher described by `spec`, see [`GameLauncher`].
+/// `flash_player` replaces the default projector for `auto` and `flash`.
+pub fn from_spec(spec: Option<&str>, flash_player: Option<&Path>) -> Result<Box<dyn GameLauncher>> {
+    let spec = spec.map(str::trim).unwrap_or_default();
+    let (kind, rest) = spec.split_once(char::is_whitespace).unwrap_or((spec, ""));
+    let rest = rest.trim().trim_matches('"');
+    let flash = match flash_player {
+        Some(path) => FlashProjector { path: crate::exe_dir().join(path) },
+        None => FlashProjector::default(),
+    };
+
+    let launcher: Box<dyn GameLauncher> = match kind.to_ascii_lowercase().as_str() {
+        "" | "auto" => Box::new(AutoLauncher { flash }),
+        "system" => Box::new(SystemLauncher),
+        "flash" if rest.is_empty() => Box::new(flash),
+        "flash" => Box::new(FlashProjector { path: rest.into() }),
+        "roadclient" if rest.is_empty() => {
+            return Err(anyhow!("roadclient launcher needs the client path"))
+        }
+        "roadclient" => Box::new(RoadclientHandler { path: rest.into() }),
+        "command" => Box::new(CommandTemplate {
+            template: spec[kind.len()..].trim().to_owned(),
+        }),
+        other => return Err(anyhow!("unknown launcher {:?}", other)),
+    };
+    Ok(launcher)
+}
+
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut pending = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                pending = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if pending {
+                    args.push(std::mem::take(&mut current));
+                    pending = false;
+                }
+            }
+            c => {
+                current.push(c);
+                pending = true;
+            }
+        }
+    }
+    if pending {
+        args.push(current);
+    }
+    args
+}
+
@@ -1,10 +1,36 @@
-use std::{collections::HashMap, io::Read, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Result};
-use reqwest::cookie::Jar;
+use reqwest::cookie::{CookieStore, Jar};
 use serde::{Deserialize, Serialize};
 use redb::ReadableTable;
 
+pub mod cassette;
+pub mod ipc;
+pub mod jobs;
+pub mod launcher;
+pub mod logging;
+#[cfg(feature = "cli")]
+pub mod password;
+pub mod redact;
+pub mod sar;
+pub mod settings;
+pub mod tools;
+
+pub use cassette::{Cassette, CassetteMode};
+pub use launcher::{GameLauncher, GameProcess};
+pub use settings::{Location, Settings};
+pub use tools::ToolInfo;
+use cassette::{Interaction, RecordedRequest, RecordedResponse, Tape};
+
 // ===== Data Types =====
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,11 +38,137 @@ pub struct UserInfo {
     pub username: String,
     pub password: String,
     pub strategy: String,
+    /// Default server ID, logged into when no other is picked. Always one
+    /// of `servers` once stored.
     pub server: String,
+    /// Every server the login plays on. Empty in records from before
+    /// accounts had several servers, see [`UserInfo::normalize`].
+    #[serde(default)]
+    pub servers: Vec<ServerEntry>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub nickname: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "deserialize_timestamp")]
     pub last_used: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// How to open the game, see [`launcher::from_spec`]. Falls back to the
+    /// strategy's `-- Launcher:` metadata.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launcher: Option<String>,
+}
+
+/// One server of an account, see [`UserInfo::servers`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerEntry {
+    pub server: String,
+    /// Character name on this server, shown instead of the account's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "deserialize_timestamp")]
+    pub last_used: Option<u64>,
+}
+
+impl UserInfo {
+    /// Check the fields every stored account needs.
+    pub fn validate(&self) -> Result<()> {
+        if self.username.trim().is_empty() {
+            return Err(DataError::Invalid("username is empty".to_owned()).into());
+        }
+        if self.server.trim().is_empty() || self.servers.iter().any(|entry| entry.server.trim().is_empty()) {
+            return Err(DataError::Invalid("server is empty".to_owned()).into());
+        }
+        Ok(())
+    }
+
+    /// Make `servers` list each server once, the default `server` included.
+    /// A record from before accounts had several servers gets an entry for
+    /// its only server that carries its `last_used`.
+    pub fn normalize(&mut self) {
+        if self.server.trim().is_empty() {
+            if let Some(first) = self.servers.first() {
+                self.server = first.server.clone();
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        self.servers.retain(|entry| seen.insert(entry.server.clone()));
+        if !seen.contains(&self.server) {
+            let last_used = if self.servers.is_empty() { self.last_used } else { None };
+            self.servers.insert(0, ServerEntry { server: self.server.clone(), nickname: None, last_used });
+        }
+    }
+
+    /// The entry of `server`, of the default server when empty.
+    pub fn server_entry(&self, server: &str) -> Option<&ServerEntry> {
+        let server = if server.is_empty() { self.server.as_str() } else { server };
+        self.servers.iter().find(|entry| entry.server == server)
+    }
+
+    /// What makes two accounts the same login: strategy and username,
+    /// ignoring case and surrounding spaces. The server is not part of it,
+    /// one account holds every server of a login.
+    pub fn login_key(&self) -> String {
+        format!("{}\n{}", self.strategy.trim(), self.username.trim()).to_lowercase()
+    }
+}
+
+/// Errors callers tell apart from I/O and database failures. They travel
+/// inside `anyhow::Error`; find them with `downcast_ref`.
+#[derive(Debug)]
+pub enum DataError {
+    /// Data that can't be stored as given.
+    Invalid(String),
+    AccountNotFound(uuid::Uuid),
+    /// Another account, this one, already has the same login.
+    Duplicate(uuid::Uuid),
+    NotInTrash(uuid::Uuid),
+    UnknownStrategy(String),
+}
+
+impl std::fmt::Display for DataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataError::Invalid(reason) => write!(f, "{}", reason),
+            DataError::AccountNotFound(uuid) => write!(f, "account {} does not exist", uuid),
+            DataError::Duplicate(uuid) => write!(f, "account {} already has this login", uuid),
+            DataError::NotInTrash(uuid) => write!(f, "account {} is not in the trash", uuid),
+            DataError::UnknownStrategy(name) => write!(f, "strategy {} does not exist", name),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+/// An account in the trash, see [`StoreEngine::remove`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedUser {
+    pub user: UserInfo,
+    /// Milliseconds since the epoch, like [`UserInfo::last_used`].
+    pub deleted_at: u64,
+}
+
+/// One login of an account, see [`StoreEngine::record_login`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginRecord {
+    /// Milliseconds since the epoch, like [`UserInfo::last_used`].
+    pub at: u64,
+    /// Server ID logged into, empty in records older than [`ServerEntry`].
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub server: String,
+    /// [`LoginOutcome::kind`].
+    pub outcome: String,
+    /// The redacted error of failed logins. Game URLs are not kept.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+}
+
+impl LoginRecord {
+    pub fn new(server: &str, outcome: &LoginOutcome) -> Self {
+        let message = match outcome {
+            LoginOutcome::Failed { message, .. } => message.clone(),
+            _ => String::new(),
+        };
+        Self { at: now_millis(), server: server.to_owned(), outcome: outcome.kind().to_owned(), message }
+    }
 }
 
 fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
@@ -33,22 +185,138 @@ where
 
 pub struct StoreEngine {
     db: redb::Database,
+    created: bool,
 }
 
 const TABLE: redb::TableDefinition<&str, &str> = redb::TableDefinition::new("users");
+const TOOLS_TABLE: redb::TableDefinition<&str, &str> = redb::TableDefinition::new("tools");
+const TRASH_TABLE: redb::TableDefinition<&str, &str> = redb::TableDefinition::new("trash");
+const HISTORY_TABLE: redb::TableDefinition<&str, &str> = redb::TableDefinition::new("history");
+/// [`UserInfo::login_key`] to the UUID of the only account with that login.
+const LOGIN_INDEX: redb::TableDefinition<&str, &str> = redb::TableDefinition::new("logins");
+/// The index before logins left the server out of their key.
+const OLD_LOGIN_INDEX: redb::TableDefinition<&str, &str> = redb::TableDefinition::new("login_index");
+
+/// Logins kept per account by [`StoreEngine::record_login`].
+pub const HISTORY_LIMIT: usize = 20;
 
 impl StoreEngine {
+    /// Open an existing database at `path` without creating it or migrating
+    /// it, so a wrong path is reported instead of leaving an empty database
+    /// behind. redb has no read-only mode: this takes the same exclusive
+    /// lock as the launcher and fails while its window is open.
+    pub fn open(path: &std::path::Path) -> Result<Self> {
+        if !path.is_file() {
+            return Err(anyhow!("no such database"));
+        }
+        let db = redb::Database::open(path)?;
+        Ok(StoreEngine { db, created: false })
+    }
+
+    /// Open the database at `path`, creating it on first run.
     pub fn create(path: &str) -> Result<Self> {
+        let created = !std::path::Path::new(path).exists();
         let db = redb::Database::create(path)?;
+
+        // Bancos antigos ainda nao tem a tabela de ferramentas: semear com a regua
+        let seed_tools = match db.begin_read() {
+            Ok(read_txn) => read_txn.open_table(TOOLS_TABLE).is_err(),
+            Err(_) => true,
+        };
+        // Nem o indice de logins: montar a partir das contas existentes
+        let build_index = match db.begin_read() {
+            Ok(read_txn) => read_txn.open_table(LOGIN_INDEX).is_err(),
+            Err(_) => true,
+        };
         
         // Garantir que a tabela existe
         let write_txn = db.begin_write()?;
         {
-            let _table = write_txn.open_table(TABLE)?;
+            let mut table = write_txn.open_table(TABLE)?;
+            let _trash = write_txn.open_table(TRASH_TABLE)?;
+            let _history = write_txn.open_table(HISTORY_TABLE)?;
+
+            // Contas de um servidor so: passar o servidor para a lista `servers`
+            let mut single = Vec::new();
+            for (key, value) in table.iter()? {
+                if let Ok(user) = serde_json::from_str::<UserInfo>(value.value()) {
+                    if user.servers.is_empty() {
+                        single.push((key.value().to_owned(), user));
+                    }
+                }
+            }
+            for (key, mut user) in single {
+                user.normalize();
+                let json = serde_json::to_string(&user)?;
+                table.insert(key.as_str(), json.as_str())?;
+            }
+
+            let mut index = write_txn.open_table(LOGIN_INDEX)?;
+            if build_index {
+                write_txn.delete_table(OLD_LOGIN_INDEX)?;
+                // Duplicatas ja existentes ficam fora do indice ate serem mescladas
+                let mut logins = Vec::new();
+                for (key, value) in table.iter()? {
+                    if let Ok(user) = serde_json::from_str::<UserInfo>(value.value()) {
+                        logins.push((user.login_key(), key.value().to_owned()));
+                    }
+                }
+                for (login, key) in logins {
+                    if index.get(login.as_str())?.is_none() {
+                        index.insert(login.as_str(), key.as_str())?;
+                    }
+                }
+            }
+            let mut tools = write_txn.open_table(TOOLS_TABLE)?;
+            if seed_tools {
+                let key = uuid::Uuid::new_v4().to_string();
+                let json = serde_json::to_string(&ToolInfo::reguinha())?;
+                tools.insert(key.as_str(), json.as_str())?;
+            }
         }
         write_txn.commit()?;
         
-        Ok(Self { db })
+        Ok(Self { db, created })
+    }
+
+    /// Whether the database file did not exist before [`StoreEngine::create`].
+    pub fn is_new(&self) -> bool {
+        self.created
+    }
+
+    /// Copy the accounts of another database file into this one, keeping
+    /// the ones already here when UUIDs or logins collide. Returns how many
+    /// were added.
+    pub fn import_users(&mut self, path: &str) -> Result<usize> {
+        let other = redb::Database::open(path)?;
+        let mut users = Vec::new();
+        {
+            let read_txn = other.begin_read()?;
+            let table = read_txn.open_table(TABLE)?;
+            for (key, value) in table.iter()? {
+                let key_str: &str = key.value();
+                let value_str: &str = value.value();
+                if let (Ok(uuid), Ok(user)) = (
+                    uuid::Uuid::parse_str(key_str),
+                    serde_json::from_str::<UserInfo>(value_str),
+                ) {
+                    users.push((uuid, user));
+                }
+            }
+        }
+
+        let mut added = 0;
+        for (uuid, user) in users {
+            if self.get_user(&uuid).is_some() {
+                continue;
+            }
+            match self.insert(&uuid, &user) {
+                Ok(()) => added += 1,
+                Err(e) if matches!(e.downcast_ref::<DataError>(), Some(DataError::Duplicate(_))) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(added)
     }
 
     pub fn get_user(&self, uuid: &uuid::Uuid) -> Option<UserInfo> {
@@ -74,8 +342,8 @@ impl StoreEngine {
             Err(_) => return users,
         };
         
-        if let Ok(mut iter) = table.iter() {
-            while let Some((key, value)) = iter.next() {
+        if let Ok(iter) = table.iter() {
+            for (key, value) in iter {
                 let key_str: &str = key.value();
                 let value_str: &str = value.value();
                 
@@ -91,22 +359,427 @@ impl StoreEngine {
         users
     }
 
+    /// Look an account up by UUID, nickname or username (case-insensitive).
+    pub fn find(&self, key: &str) -> Result<(uuid::Uuid, UserInfo)> {
+        if let Ok(uuid) = uuid::Uuid::parse_str(key) {
+            if let Some(user) = self.get_user(&uuid) {
+                return Ok((uuid, user));
+            }
+        }
+
+        let matches: Vec<(uuid::Uuid, UserInfo)> = self
+            .users()
+            .into_iter()
+            .filter(|(_, user)| {
+                user.nickname
+                    .as_deref()
+                    .is_some_and(|nickname| nickname.eq_ignore_ascii_case(key))
+                    || user.username.eq_ignore_ascii_case(key)
+            })
+            .collect();
+
+        match matches.len() {
+            0 => Err(anyhow!("no account matches {}", key)),
+            1 => Ok(matches.into_iter().next().unwrap()),
+            n => Err(anyhow!("{} accounts match {}, use the UUID instead", n, key)),
+        }
+    }
+
+    /// All accounts carrying `tag`.
+    pub fn tagged(&self, tag: &str) -> Vec<(uuid::Uuid, UserInfo)> {
+        self.users()
+            .into_iter()
+            .filter(|(_, user)| user.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            .collect()
+    }
+
+    /// The account that already has the login of `user`, if any.
+    pub fn find_login(&self, user: &UserInfo) -> Option<(uuid::Uuid, UserInfo)> {
+        let login = user.login_key();
+        self.users().into_iter().find(|(_, other)| other.login_key() == login)
+    }
+
+    /// Groups of accounts sharing a login, left over from before logins had
+    /// to be unique. Each group is sorted by most recent use.
+    pub fn duplicates(&self) -> Vec<Vec<(uuid::Uuid, UserInfo)>> {
+        let mut groups: BTreeMap<String, Vec<(uuid::Uuid, UserInfo)>> = BTreeMap::new();
+        for (uuid, user) in self.users() {
+            groups.entry(user.login_key()).or_default().push((uuid, user));
+        }
+        groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort_by_key(|(_, user)| std::cmp::Reverse(user.last_used));
+                group
+            })
+            .collect()
+    }
+
+    /// Fold `others` into `keep` and move them to the trash. `keep` gains
+    /// their servers, tags, login history and most recent use, and takes
+    /// their nickname, launcher or password where it has none. A server
+    /// new to `keep` is named after the account it came from. Repeated ids
+    /// and `keep` itself are ignored in `others`.
+    pub fn merge(&mut self, keep: &uuid::Uuid, others: &[uuid::Uuid]) -> Result<UserInfo> {
+        let write_txn = self.db.begin_write()?;
+        let user = {
+            let mut table = write_txn.open_table(TABLE)?;
+            let mut trash = write_txn.open_table(TRASH_TABLE)?;
+            let mut history = write_txn.open_table(HISTORY_TABLE)?;
+            let mut index = write_txn.open_table(LOGIN_INDEX)?;
+            let key = keep.to_string();
+            let mut user: UserInfo = match table.get(key.as_str())? {
+                Some(value) => serde_json::from_str(value.value())?,
+                None => return Err(DataError::AccountNotFound(*keep).into()),
+            };
+            let mut records: Vec<LoginRecord> = match history.get(key.as_str())? {
+                Some(value) => serde_json::from_str(value.value()).unwrap_or_default(),
+                None => Vec::new(),
+            };
+
+            let mut merged: Vec<&uuid::Uuid> = Vec::new();
+            for uuid in others {
+                if uuid != keep && !merged.contains(&uuid) {
+                    merged.push(uuid);
+                }
+            }
+
+            for uuid in merged {
+                let other_key = uuid.to_string();
+                let other: UserInfo = match table.remove(other_key.as_str())? {
+                    Some(value) => serde_json::from_str(value.value())?,
+                    None => return Err(DataError::AccountNotFound(*uuid).into()),
+                };
+                unindex(&mut index, &other.login_key(), &other_key)?;
+
+                for entry in &other.servers {
+                    match user.servers.iter_mut().find(|own| own.server == entry.server) {
+                        Some(own) => {
+                            own.last_used = own.last_used.max(entry.last_used);
+                            if own.nickname.is_none() {
+                                own.nickname = entry.nickname.clone();
+                            }
+                        }
+                        None => {
+                            let mut entry = entry.clone();
+                            if entry.nickname.is_none() && entry.server == other.server {
+                                entry.nickname = other.nickname.clone();
+                            }
+                            user.servers.push(entry);
+                        }
+                    }
+                }
+                for tag in &other.tags {
+                    if !user.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                        user.tags.push(tag.clone());
+                    }
+                }
+                if user.nickname.is_none() {
+                    user.nickname = other.nickname.clone();
+                }
+                if user.launcher.is_none() {
+                    user.launcher = other.launcher.clone();
+                }
+                if user.password.is_empty() {
+                    user.password = other.password.clone();
+                }
+                user.last_used = user.last_used.max(other.last_used);
+                if let Some(value) = history.get(other_key.as_str())? {
+                    records.extend(serde_json::from_str::<Vec<LoginRecord>>(value.value()).unwrap_or_default());
+                }
+
+                // Continua na lixeira com o proprio historico, caso a mescla tenha sido engano
+                let json = serde_json::to_string(&DeletedUser { user: other, deleted_at: now_millis() })?;
+                trash.insert(other_key.as_str(), json.as_str())?;
+            }
+
+            records.sort_by_key(|record| std::cmp::Reverse(record.at));
+            records.truncate(HISTORY_LIMIT);
+            let json = serde_json::to_string(&records)?;
+            history.insert(key.as_str(), json.as_str())?;
+
+            let json = serde_json::to_string(&user)?;
+            table.insert(key.as_str(), json.as_str())?;
+            let login = user.login_key();
+            let owner = index.get(login.as_str())?.map(|value| value.value().to_owned());
+            if owner.is_none() {
+                index.insert(login.as_str(), key.as_str())?;
+            }
+            user
+        };
+        write_txn.commit()?;
+        Ok(user)
+    }
+
+    /// Store an account. Fails with [`DataError::Duplicate`] when another
+    /// account has the same [`UserInfo::login_key`].
     pub fn insert(&mut self, uuid: &uuid::Uuid, user: &UserInfo) -> Result<()> {
+        let mut user = user.clone();
+        user.normalize();
+        user.validate()?;
         let write_txn = self.db.begin_write()?;
         {
             let mut table = write_txn.open_table(TABLE)?;
+            let mut index = write_txn.open_table(LOGIN_INDEX)?;
             let key = uuid.to_string();
-            let json = serde_json::to_string(user)?;
+            let login = user.login_key();
+            let previous = table
+                .get(key.as_str())?
+                .and_then(|value| serde_json::from_str::<UserInfo>(value.value()).ok())
+                .map(|previous| previous.login_key());
+            let owner = index.get(login.as_str())?.map(|value| value.value().to_owned());
+            let owner = owner.filter(|owner| *owner != key);
+            // Duplicata de um banco antigo, fora do indice: pode ser editada
+            // enquanto o login continuar o mesmo, ate ser mesclada
+            let legacy = owner.is_some() && previous.as_deref() == Some(login.as_str());
+            if let Some(owner) = owner.as_ref().filter(|_| !legacy) {
+                return Err(DataError::Duplicate(uuid::Uuid::parse_str(owner)?).into());
+            }
+
+            // Login alterado: liberar o antigo
+            if let Some(previous) = previous.filter(|previous| *previous != login) {
+                unindex(&mut index, &previous, &key)?;
+            }
+
+            let json = serde_json::to_string(&user)?;
             table.insert(key.as_str(), json.as_str())?;
+            if !legacy {
+                index.insert(login.as_str(), key.as_str())?;
+            }
         }
         write_txn.commit()?;
         Ok(())
     }
 
+    /// Store how a login of an account ended and mark the account, and the
+    /// server of the login, as used at that time.
+    pub fn record_login(&mut self, uuid: &uuid::Uuid, record: LoginRecord) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE)?;
+            let mut history = write_txn.open_table(HISTORY_TABLE)?;
+            let key = uuid.to_string();
+
+            let mut user: UserInfo = match table.get(key.as_str())? {
+                Some(value) => serde_json::from_str(value.value())?,
+                None => return Err(DataError::AccountNotFound(*uuid).into()),
+            };
+            user.last_used = Some(record.at);
+            let server = if record.server.is_empty() { user.server.clone() } else { record.server.clone() };
+            if let Some(entry) = user.servers.iter_mut().find(|entry| entry.server == server) {
+                entry.last_used = Some(record.at);
+            }
+            let json = serde_json::to_string(&user)?;
+            table.insert(key.as_str(), json.as_str())?;
+
+            let mut records: Vec<LoginRecord> = match history.get(key.as_str())? {
+                Some(value) => serde_json::from_str(value.value()).unwrap_or_default(),
+                None => Vec::new(),
+            };
+            records.insert(0, record);
+            records.truncate(HISTORY_LIMIT);
+            let json = serde_json::to_string(&records)?;
+            history.insert(key.as_str(), json.as_str())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Last logins of an account, newest first.
+    pub fn history(&self, uuid: &uuid::Uuid) -> Vec<LoginRecord> {
+        let read = || -> Option<Vec<LoginRecord>> {
+            let read_txn = self.db.begin_read().ok()?;
+            let table = read_txn.open_table(HISTORY_TABLE).ok()?;
+            let key = uuid.to_string();
+            let value = table.get(key.as_str()).ok()??;
+            serde_json::from_str(value.value()).ok()
+        };
+        read().unwrap_or_default()
+    }
+
+    /// Move an account to the trash, from where [`StoreEngine::restore`]
+    /// brings it back until it is purged.
     pub fn remove(&mut self, uuid: &uuid::Uuid) -> Result<()> {
         let write_txn = self.db.begin_write()?;
         {
             let mut table = write_txn.open_table(TABLE)?;
+            let mut trash = write_txn.open_table(TRASH_TABLE)?;
+            let mut index = write_txn.open_table(LOGIN_INDEX)?;
+            let key = uuid.to_string();
+            let user: UserInfo = match table.remove(key.as_str())? {
+                Some(value) => serde_json::from_str(value.value())?,
+                None => return Err(DataError::AccountNotFound(*uuid).into()),
+            };
+            unindex(&mut index, &user.login_key(), &key)?;
+            let json = serde_json::to_string(&DeletedUser { user, deleted_at: now_millis() })?;
+            trash.insert(key.as_str(), json.as_str())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Accounts in the trash, most recently deleted first.
+    pub fn trashed(&self) -> Vec<(uuid::Uuid, DeletedUser)> {
+        let mut deleted = Vec::new();
+
+        let read_txn = match self.db.begin_read() {
+            Ok(txn) => txn,
+            Err(_) => return deleted,
+        };
+        let table = match read_txn.open_table(TRASH_TABLE) {
+            Ok(t) => t,
+            Err(_) => return deleted,
+        };
+
+        if let Ok(iter) = table.iter() {
+            for (key, value) in iter {
+                if let (Ok(uuid), Ok(user)) = (
+                    uuid::Uuid::parse_str(key.value()),
+                    serde_json::from_str::<DeletedUser>(value.value()),
+                ) {
+                    deleted.push((uuid, user));
+                }
+            }
+        }
+
+        deleted.sort_by_key(|(_, d)| std::cmp::Reverse(d.deleted_at));
+        deleted
+    }
+
+    /// Move an account back from the trash. Fails with
+    /// [`DataError::Duplicate`] when another account took its login since.
+    pub fn restore(&mut self, uuid: &uuid::Uuid) -> Result<UserInfo> {
+        let write_txn = self.db.begin_write()?;
+        let user = {
+            let mut table = write_txn.open_table(TABLE)?;
+            let mut trash = write_txn.open_table(TRASH_TABLE)?;
+            let mut index = write_txn.open_table(LOGIN_INDEX)?;
+            let key = uuid.to_string();
+            let mut deleted: DeletedUser = match trash.remove(key.as_str())? {
+                Some(value) => serde_json::from_str(value.value())?,
+                None => return Err(DataError::NotInTrash(*uuid).into()),
+            };
+            deleted.user.normalize();
+            let login = deleted.user.login_key();
+            let owner = index.get(login.as_str())?.map(|value| value.value().to_owned());
+            if let Some(owner) = owner {
+                return Err(DataError::Duplicate(uuid::Uuid::parse_str(&owner)?).into());
+            }
+            let json = serde_json::to_string(&deleted.user)?;
+            table.insert(key.as_str(), json.as_str())?;
+            index.insert(login.as_str(), key.as_str())?;
+            deleted.user
+        };
+        write_txn.commit()?;
+        Ok(user)
+    }
+
+    /// Erase an account in the trash for good.
+    pub fn purge(&mut self, uuid: &uuid::Uuid) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut trash = write_txn.open_table(TRASH_TABLE)?;
+            let mut history = write_txn.open_table(HISTORY_TABLE)?;
+            let key = uuid.to_string();
+            if trash.remove(key.as_str())?.is_none() {
+                return Err(DataError::NotInTrash(*uuid).into());
+            }
+            history.remove(key.as_str())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Erase the accounts older than [`Settings::trash_days`], logging
+    /// instead of failing since it runs on startup.
+    pub fn purge_expired(&mut self, settings: &Settings) {
+        let Some(age) = settings.trash_retention() else { return };
+        match self.purge_trash(age) {
+            Ok(0) => {}
+            Ok(count) => log::info!("{} conta(s) apagada(s) da lixeira após {} dias", count, settings.trash_days),
+            Err(e) => log::warn!("Erro ao esvaziar a lixeira: {:#}", e),
+        }
+    }
+
+    /// Erase the accounts deleted more than `age` ago, all of them for a
+    /// zero `age`. Returns how many were erased.
+    pub fn purge_trash(&mut self, age: Duration) -> Result<usize> {
+        let cutoff = now_millis().saturating_sub(age.as_millis() as u64);
+        let expired: Vec<uuid::Uuid> = self
+            .trashed()
+            .into_iter()
+            .filter(|(_, deleted)| age.is_zero() || deleted.deleted_at < cutoff)
+            .map(|(uuid, _)| uuid)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut trash = write_txn.open_table(TRASH_TABLE)?;
+            let mut history = write_txn.open_table(HISTORY_TABLE)?;
+            for uuid in &expired {
+                let key = uuid.to_string();
+                trash.remove(key.as_str())?;
+                history.remove(key.as_str())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(expired.len())
+    }
+
+    pub fn tools(&self) -> Vec<(uuid::Uuid, ToolInfo)> {
+        let mut tools = Vec::new();
+
+        let read_txn = match self.db.begin_read() {
+            Ok(txn) => txn,
+            Err(_) => return tools,
+        };
+        let table = match read_txn.open_table(TOOLS_TABLE) {
+            Ok(t) => t,
+            Err(_) => return tools,
+        };
+
+        if let Ok(iter) = table.iter() {
+            for (key, value) in iter {
+                if let (Ok(uuid), Ok(tool)) = (
+                    uuid::Uuid::parse_str(key.value()),
+                    serde_json::from_str::<ToolInfo>(value.value()),
+                ) {
+                    tools.push((uuid, tool));
+                }
+            }
+        }
+
+        tools
+    }
+
+    pub fn get_tool(&self, uuid: &uuid::Uuid) -> Option<ToolInfo> {
+        let read_txn = self.db.begin_read().ok()?;
+        let table = read_txn.open_table(TOOLS_TABLE).ok()?;
+        let key = uuid.to_string();
+        let value = table.get(key.as_str()).ok()??;
+        serde_json::from_str(value.value()).ok()
+    }
+
+    pub fn insert_tool(&mut self, uuid: &uuid::Uuid, tool: &ToolInfo) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TOOLS_TABLE)?;
+            let key = uuid.to_string();
+            let json = serde_json::to_string(tool)?;
+            table.insert(key.as_str(), json.as_str())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_tool(&mut self, uuid: &uuid::Uuid) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TOOLS_TABLE)?;
             let key = uuid.to_string();
             table.remove(key.as_str())?;
         }
@@ -115,14 +788,83 @@ impl StoreEngine {
     }
 }
 
+/// Drop `login` from the index if `key` is the account holding it.
+fn unindex(index: &mut redb::Table<&str, &str>, login: &str, key: &str) -> Result<()> {
+    let owner = index.get(login)?.map(|value| value.value().to_owned());
+    if owner.as_deref() == Some(key) {
+        index.remove(login)?;
+    }
+    Ok(())
+}
+
+/// Milliseconds since the Unix epoch, the unit of every stored timestamp.
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ===== Paths =====
+
+/// Account database file name, see [`Location::database`].
+pub const DATABASE_FILE: &str = "userdata.redb";
+
+/// Directory of the running executable, home of the bundled `scripts/` and
+/// `tools/`. User data lives in [`Location::data_dir`].
+pub fn exe_dir() -> std::path::PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .unwrap_or_else(|| std::env::current_dir().unwrap())
+}
+
+// ===== Game Launch =====
+
+/// Whether a strategy result is something to launch rather than a message.
+pub fn is_game_url(result: &str) -> bool {
+    result.starts_with("http") || result.starts_with("roadclient://")
+}
+
+/// Launcher spec for `user`: the account's own setting, else the strategy's
+/// `-- Launcher:` metadata, else [`Settings::default_launcher`] once passed
+/// to [`launch_game`].
+pub fn launcher_spec(user: &UserInfo, script: &str) -> Option<String> {
+    user.launcher
+        .clone()
+        .filter(|spec| !spec.trim().is_empty())
+        .or_else(|| StrategyMetadata::parse(script).get("launcher").map(str::to_owned))
+}
+
+/// Open the game for a URL returned by a strategy without waiting for it.
+pub fn launch_game(url: &str, launcher: Option<&str>, settings: &Settings) -> Result<GameProcess> {
+    let spec = launcher.or(settings.default_launcher.as_deref());
+    let launcher = launcher::from_spec(spec, settings.flash_player.as_deref())?;
+    log::info!("Abrindo {} com o launcher {}", url, launcher.name());
+    launcher.launch(url)
+}
+
 // ===== Strategy System =====
 
+/// Strategies compiled into the binaries, so they work without a
+/// `scripts/` folder. Loose scripts with the same name replace them.
+pub const BUNDLED_STRATEGIES: &[(&str, &str)] = &[("337.lua", include_str!("../scripts/337.lua"))];
+
 #[derive(Default)]
 pub struct Strategy {
     scripts: HashMap<String, String>,
 }
 
 impl Strategy {
+    /// Only the [`BUNDLED_STRATEGIES`].
+    pub fn bundled() -> Self {
+        let scripts = BUNDLED_STRATEGIES
+            .iter()
+            .map(|(name, script)| (name.to_string(), script.to_string()))
+            .collect();
+        Strategy { scripts }
+    }
+
     pub fn new(pattern: &str) -> Self {
         let mut strategy = Strategy::default();
         strategy.load(pattern);
@@ -131,12 +873,10 @@ impl Strategy {
 
     /// Load scripts from path that match a glob pattern.
     pub fn load(&mut self, pattern: &str) {
-        for entry in glob::glob(pattern).expect("Failed to read glob pattern") {
-            if let Ok(path) = entry {
-                let file_name = path.file_name().unwrap().to_str().unwrap().to_owned();
-                let script = std::fs::read_to_string(path).unwrap();
-                self.scripts.insert(file_name, script);
-            }
+        for path in glob::glob(pattern).expect("Failed to read glob pattern").flatten() {
+            let file_name = path.file_name().unwrap().to_str().unwrap().to_owned();
+            let script = std::fs::read_to_string(path).unwrap();
+            self.scripts.insert(file_name, script);
         }
     }
 
@@ -149,11 +889,231 @@ impl Strategy {
         let script = self
             .scripts
             .get(name)
-            .ok_or_else(|| anyhow!(format!("stratrgy {} do not exist", name)))?
+            .ok_or_else(|| DataError::UnknownStrategy(name.to_owned()))?
             .to_owned();
 
         Ok(script)
     }
+
+    pub fn metadata(&self, name: &str) -> Result<StrategyMetadata> {
+        Ok(StrategyMetadata::parse(&self.get(name)?))
+    }
+}
+
+/// Leading `-- Key: Value` comments of a strategy script, e.g.
+/// `-- Strategy: 337.com ddtank`. Keys are lowercased.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StrategyMetadata {
+    pub fields: BTreeMap<String, String>,
+}
+
+impl StrategyMetadata {
+    pub fn parse(script: &str) -> Self {
+        let fields = script
+            .lines()
+            .map(str::trim)
+            .take_while(|line| line.is_empty() || line.starts_with("--"))
+            .filter_map(|line| line.trim_start_matches('-').split_once(':'))
+            .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_owned()))
+            .filter(|(key, _)| !key.is_empty() && !key.contains(' '))
+            .collect();
+        Self { fields }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+}
+
+/// One entry of a strategy's `servers()` list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub range: String,
+}
+
+/// Result of [`check_strategy`].
+#[derive(Debug, Clone, Serialize)]
+pub struct StrategyCheck {
+    /// Whether the script also defines `servers()`.
+    pub has_servers: bool,
+}
+
+/// Load a script and make sure it defines a `login` entry point.
+pub fn check_strategy(script: &str) -> Result<StrategyCheck> {
+    let lua = new_lua(Arc::new(RunContext::default()))?;
+    lua.load(script).set_name("=strategy").exec()?;
+    let globals = lua.globals();
+    if !matches!(globals.get::<_, mlua::Value>("login")?, mlua::Value::Function(_)) {
+        return Err(anyhow!("script does not define a login(username, password, server) function"));
+    }
+    let has_servers = matches!(globals.get::<_, mlua::Value>("servers")?, mlua::Value::Function(_));
+    Ok(StrategyCheck { has_servers })
+}
+
+/// Call a strategy's optional `servers()` function.
+pub fn strategy_servers(script: &str) -> Result<Vec<ServerInfo>> {
+    use mlua::LuaSerdeExt;
+
+    let lua = new_lua(Arc::new(RunContext::default()))?;
+    lua.load(script).set_name("=strategy").exec()?;
+    let servers: Option<mlua::Function> = lua.globals().get("servers")?;
+    let servers = servers.ok_or_else(|| anyhow!("script does not define a servers() function"))?;
+    let list: mlua::Value = servers.call(())?;
+    Ok(lua.from_value(list)?)
+}
+
+/// Lets another thread stop a running strategy.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// How a login ended.
+#[derive(Debug, Clone)]
+pub enum LoginOutcome {
+    /// The strategy returned, usually with a game URL.
+    Success(String),
+    /// The strategy failed; the message is already redacted. `transient`
+    /// failures (network errors, 5xx responses) are worth retrying.
+    Failed { message: String, transient: bool },
+    Cancelled,
+    TimedOut,
+}
+
+impl LoginOutcome {
+    /// Short name used by the UI: `ok`, `error`, `cancelled` or `timeout`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LoginOutcome::Success(_) => "ok",
+            LoginOutcome::Failed { .. } => "error",
+            LoginOutcome::Cancelled => "cancelled",
+            LoginOutcome::TimedOut => "timeout",
+        }
+    }
+
+    /// The strategy result or error message; empty when interrupted.
+    pub fn message(&self) -> &str {
+        match self {
+            LoginOutcome::Success(message) | LoginOutcome::Failed { message, .. } => message,
+            LoginOutcome::Cancelled | LoginOutcome::TimedOut => "",
+        }
+    }
+
+    /// Whether trying again later might succeed. A bad password or a
+    /// non-zero `error` code from the server will not, and a timed out login
+    /// has already used up its whole time limit.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            LoginOutcome::Failed { transient, .. } => *transient,
+            LoginOutcome::Success(_) | LoginOutcome::Cancelled | LoginOutcome::TimedOut => false,
+        }
+    }
+}
+
+/// How often and how patiently a login is retried after transient failures.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Attempts after the first one.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every further one.
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry.
+    pub fn none() -> Self {
+        Self {
+            retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// The default policy, adjusted by the script's `-- Retries: <n>` and
+    /// `-- Backoff: <seconds>` metadata.
+    pub fn for_script(script: &str) -> Self {
+        let metadata = StrategyMetadata::parse(script);
+        let mut policy = Self::default();
+        if let Some(retries) = metadata.get("retries").and_then(|r| r.trim().parse().ok()) {
+            policy.retries = retries;
+        }
+        if let Some(backoff) = metadata.get("backoff").and_then(|b| b.trim().parse::<f64>().ok()) {
+            if backoff.is_finite() && backoff >= 0.0 {
+                policy.backoff = Duration::from_secs_f64(backoff);
+            }
+        }
+        policy
+    }
+
+    /// Delay before retry number `retry` (1 for the first retry).
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// One failed attempt that is about to be retried.
+#[derive(Debug, Clone)]
+pub struct RetryAttempt {
+    /// The attempt that just failed, starting at 1.
+    pub attempt: u32,
+    /// Total attempts the policy allows.
+    pub max_attempts: u32,
+    pub delay: Duration,
+    pub outcome: LoginOutcome,
+}
+
+/// Knobs for a single strategy run.
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOptions {
+    /// Record the run's HTTP traffic to a cassette, or replay it from one.
+    pub cassette: Option<CassetteMode>,
+    /// Checked between Lua instructions and before every HTTP request.
+    pub cancel: Option<CancelToken>,
+    /// Overall limit for the whole login, HTTP requests and retries included.
+    pub timeout: Option<Duration>,
+    /// Sent with every request, [`settings::DEFAULT_USER_AGENT`] if unset.
+    pub user_agent: Option<String>,
+}
+
+/// Overall login timeout used when nothing else is configured.
+pub const DEFAULT_LOGIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Timeout for logging in with `script`: its `-- Timeout: <seconds>` metadata,
+/// else `default`, usually [`Settings::login_timeout`].
+pub fn login_timeout(script: &str, default: Duration) -> Duration {
+    StrategyMetadata::parse(script)
+        .get("timeout")
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(default)
 }
 
 /// Execute a strategy by name
@@ -163,49 +1123,285 @@ pub fn execute_strategy(
     password: &str,
     server: &str,
 ) -> Result<String> {
+    execute_strategy_with(script, username, password, server, &ExecuteOptions::default())
+}
+
+/// Execute a strategy with explicit [`ExecuteOptions`].
+pub fn execute_strategy_with(
+    script: &str,
+    username: &str,
+    password: &str,
+    server: &str,
+    options: &ExecuteOptions,
+) -> Result<String> {
+    let tape = match &options.cassette {
+        Some(mode) => Some(Mutex::new(Tape::open(mode, &[password])?)),
+        None => None,
+    };
+    let context = Arc::new(RunContext {
+        tape,
+        secrets: redact::Secrets::new(&[password]),
+        cancel: options.cancel.clone().unwrap_or_default(),
+        deadline: options.timeout.map(|timeout| Instant::now() + timeout),
+        user_agent: options
+            .user_agent
+            .clone()
+            .unwrap_or_else(|| settings::DEFAULT_USER_AGENT.to_owned()),
+        transient: AtomicBool::new(false),
+    });
+
+    let _secret = redact::register_secret(password);
+    let result = run_login(script, username, password, server, context.clone()).map_err(|err| {
+        let message = redact::redact_with(&format!("{:#}", err), &context.secrets);
+        if context.transient.load(Ordering::SeqCst) {
+            anyhow::Error::new(TransientError(message))
+        } else {
+            anyhow!(message)
+        }
+    });
+
+    // A recording is most useful when the login failed, so save it either way.
+    let context = Arc::try_unwrap(context)
+        .map_err(|_| anyhow!("strategy context still in use after the script finished"))?;
+    if let Some(tape) = context.tape {
+        tape.into_inner()
+            .map_err(|_| anyhow!("cassette lock poisoned"))?
+            .finish()?;
+    }
+
+    result
+}
+
+/// Run a login and classify how it ended. A run stopped through
+/// `options.cancel` or `options.timeout` reports that rather than the error
+/// it was interrupted with.
+pub fn execute_login(
+    script: &str,
+    username: &str,
+    password: &str,
+    server: &str,
+    options: &ExecuteOptions,
+) -> LoginOutcome {
+    let started = Instant::now();
+    let result = execute_strategy_with(script, username, password, server, options);
+
+    if options.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+        return LoginOutcome::Cancelled;
+    }
+    match result {
+        Ok(url) => LoginOutcome::Success(url),
+        Err(_) if options.timeout.is_some_and(|timeout| started.elapsed() >= timeout) => {
+            LoginOutcome::TimedOut
+        }
+        Err(err) => LoginOutcome::Failed {
+            transient: err.is::<TransientError>(),
+            message: err.to_string(),
+        },
+    }
+}
+
+/// [`execute_login`], retrying transient failures according to `policy`.
+/// `options.timeout` bounds all attempts and backoffs together: each attempt
+/// only gets what is left of it, and no retry starts past it. `on_retry` is
+/// told about every failed attempt before waiting out its backoff; a cancel
+/// during the wait ends the login right away.
+pub fn execute_login_with_retry<F: FnMut(&RetryAttempt)>(
+    script: &str,
+    username: &str,
+    password: &str,
+    server: &str,
+    options: &ExecuteOptions,
+    policy: &RetryPolicy,
+    mut on_retry: F,
+) -> LoginOutcome {
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    let max_attempts = policy.retries.saturating_add(1);
+    let mut attempt = 1;
+    loop {
+        let attempt_options = ExecuteOptions {
+            timeout: deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())),
+            ..options.clone()
+        };
+        let outcome = execute_login(script, username, password, server, &attempt_options);
+        if attempt >= max_attempts || !outcome.is_transient() {
+            return outcome;
+        }
+
+        let delay = policy.delay(attempt);
+        if deadline.is_some_and(|deadline| Instant::now() + delay >= deadline) {
+            log::warn!("attempt {}/{} failed, no time left to retry", attempt, max_attempts);
+            return outcome;
+        }
+        let retry = RetryAttempt {
+            attempt,
+            max_attempts,
+            delay,
+            outcome,
+        };
+        log::warn!(
+            "attempt {}/{} failed, retrying in {:?}: {}",
+            retry.attempt,
+            retry.max_attempts,
+            retry.delay,
+            retry.outcome.message()
+        );
+        on_retry(&retry);
+
+        let resume_at = Instant::now() + retry.delay;
+        while Instant::now() < resume_at {
+            if options.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+                return LoginOutcome::Cancelled;
+            }
+            std::thread::sleep(Duration::from_millis(100).min(resume_at - Instant::now()));
+        }
+        attempt += 1;
+    }
+}
+
+/// Marks a strategy error caused by a failure worth retrying.
+#[derive(Debug)]
+struct TransientError(String);
+
+impl std::fmt::Display for TransientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TransientError {}
+
+fn run_login(
+    script: &str,
+    username: &str,
+    password: &str,
+    server: &str,
+    context: Arc<RunContext>,
+) -> Result<String> {
+    let lua = new_lua(context)?;
+    lua.load(script).set_name("=strategy").exec()?;
+    let login_function: mlua::Function = lua.globals().get("login")?;
+    let result = login_function.call::<_, String>((username, password, server))?;
+
+    Ok(result)
+}
+
+/// State shared by everything a strategy creates during one run.
+#[derive(Default)]
+struct RunContext {
+    tape: Option<Mutex<Tape>>,
+    /// Never written to logs or error messages as-is.
+    secrets: redact::Secrets,
+    cancel: CancelToken,
+    deadline: Option<Instant>,
+    user_agent: String,
+    /// Whether the last HTTP exchange failed in a way worth retrying:
+    /// a network error, a request timeout or a 5xx response.
+    transient: AtomicBool,
+}
+
+impl RunContext {
+    /// Error out if the run was cancelled or ran past its deadline.
+    fn check_interrupted(&self) -> Result<()> {
+        if self.cancel.is_cancelled() {
+            return Err(anyhow!("login cancelled"));
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(anyhow!("login timed out"));
+        }
+        Ok(())
+    }
+
+    /// Time left before the deadline, if there is one.
+    fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+/// A Lua state with the functions strategies rely on.
+fn new_lua(context: Arc<RunContext>) -> Result<mlua::Lua> {
     let lua = mlua::Lua::new();
-    let globals = lua.globals();
+    {
+        let hook_context = context.clone();
+        lua.set_hook(
+            mlua::HookTriggers::new().every_nth_instruction(1000),
+            move |_, _| hook_context.check_interrupted().map_err(mlua::Error::external),
+        );
+
+        let globals = lua.globals();
 
-    let agent_constructor = lua.create_function(|_, ()| Ok(Agent::new()))?;
-    globals.set("agent", agent_constructor)?;
+        // Lets long-running strategies bail out early between steps.
+        let cancel_context = context.clone();
+        let cancelled_func =
+            lua.create_function(move |_, ()| Ok(cancel_context.check_interrupted().is_err()))?;
+        globals.set("cancelled", cancelled_func)?;
 
-    let crypto_rs = lua.create_table()?;
+        let agent_constructor = lua
+            .create_function(move |_, ()| Agent::new(context.clone()).map_err(mlua::Error::external))?;
+        globals.set("agent", agent_constructor)?;
+
+        let crypto_rs = lua.create_table()?;
         let md5_func = lua.create_function(|_, input: String| {
             let digest = md5::compute(input.as_bytes());
             Ok(format!("{:x}", digest))
         })?;
 
-    crypto_rs.set("md5", md5_func)?;
-    globals.set("crypto", crypto_rs)?;
+        crypto_rs.set("md5", md5_func)?;
+        globals.set("crypto", crypto_rs)?;
+
+        // For values spliced into a URL by hand; agent:post encodes its form itself.
+        let url_encode_func = lua.create_function(|_, input: String| Ok(url_encode(&input)))?;
+        globals.set("url_encode", url_encode_func)?;
+
+        let cowv2_func =
+            lua.create_function(|_, (url, re, title): (String, String, String)| {
+                get_cookie_by_cowv2(url, re, title).map_err(mlua::Error::external)
+            })?;
+        globals.set("get_cookie_by_cowv2", cowv2_func)?;
 
-    let cowv2_func =
-        lua.create_function(|_, (url, re, title): (String, String, String)| {
-            let result = get_cookie_by_cowv2(url, re, title).unwrap();
-            Ok(result)
+        // log("message") or log("warn", "message")
+        let log_func = lua.create_function(|_, (first, second): (String, Option<String>)| {
+            let (level, message) = match second {
+                Some(message) => (first.parse().unwrap_or(log::Level::Info), message),
+                None => (log::Level::Info, first),
+            };
+            log::log!(target: "strategy", level, "{}", message);
+            Ok(())
         })?;
-    globals.set("get_cookie_by_cowv2", cowv2_func)?;
+        globals.set("log", log_func)?;
+    }
+    Ok(lua)
+}
 
-    lua.load(script).exec()?;
-    let login_function: mlua::Function = globals.get("login")?;
-    let result = login_function.call::<_, String>((username, password, server))?;
+/// Percent-encode `input` for a URL query, keeping only the RFC 3986
+/// unreserved characters as they are.
+fn url_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
 
-    Ok(result)
+/// What a strategy sees of one HTTP exchange, live or replayed.
+struct Exchange {
+    url: reqwest::Url,
+    body: String,
 }
 
 struct Agent {
     client: reqwest::blocking::Client,
     cookie_jar: Arc<Jar>,
+    context: Arc<RunContext>,
 }
 
 impl Agent {
-    fn new() -> Self {
+    fn new(context: Arc<RunContext>) -> Result<Self> {
         let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::USER_AGENT,
-            "Mozilla/4.0 (compatible; MSIE 6.0; Windows NT 5.1; .NET CLR 1.0.3705;)"
-                .parse()
-                .unwrap(),
-        );
+        headers.insert(reqwest::header::USER_AGENT, context.user_agent.parse()?);
 
         let cookie_jar: Arc<Jar> = Default::default();
         let cookie_jar1 = cookie_jar.clone();
@@ -214,48 +1410,156 @@ impl Agent {
             .default_headers(headers)
             .cookie_store(true)
             .cookie_provider(cookie_jar1)
-            .build()
-            .unwrap();
+            .build()?;
 
-        Self { client, cookie_jar }
+        Ok(Self {
+            client,
+            cookie_jar,
+            context,
+        })
+    }
+
+    fn send(&self, method: reqwest::Method, url: &str, form: Option<&HashMap<String, String>>) -> Result<Exchange> {
+        self.context.check_interrupted()?;
+        log::debug!(target: "http", "-> {} {}", method, redact::redact_with(url, &self.context.secrets));
+
+        if let Some(tape) = &self.context.tape {
+            let mut tape = tape.lock().map_err(|_| anyhow!("cassette lock poisoned"))?;
+            if tape.is_replay() {
+                // Normalise the same way a live request would before matching.
+                let url: reqwest::Url = url.parse()?;
+                let response = tape.replay(method.as_str(), url.as_str())?;
+                self.context.transient.store(response.status >= 500, Ordering::SeqCst);
+                log::debug!(
+                    target: "http",
+                    "<- {} {} (replayed)",
+                    response.status,
+                    redact::redact_with(&response.url, &self.context.secrets)
+                );
+                return Ok(Exchange {
+                    url: response.url.parse()?,
+                    body: response.body,
+                });
+            }
+        }
+
+        let mut builder = self.client.request(method, url);
+        if let Some(form) = form {
+            builder = builder.form(form);
+        }
+        if let Some(remaining) = self.context.remaining() {
+            builder = builder.timeout(remaining);
+        }
+        let request = builder.build()?;
+        let recorded_request = self.context.tape.as_ref().map(|_| RecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers: header_pairs(request.headers()),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|bytes| String::from_utf8_lossy(bytes).into_owned()),
+            cookies: self
+                .cookie_jar
+                .cookies(request.url())
+                .and_then(|value| value.to_str().ok().map(str::to_owned)),
+        });
+
+        let received = self
+            .client
+            .execute(request)
+            .and_then(|response| {
+                let status = response.status().as_u16();
+                let final_url = response.url().clone();
+                let headers = header_pairs(response.headers());
+                Ok((status, final_url, headers, response.text()?))
+            });
+        let (status, final_url, headers, body) = match received {
+            Ok(received) => received,
+            Err(err) => {
+                self.context.transient.store(true, Ordering::SeqCst);
+                return Err(err.into());
+            }
+        };
+        self.context.transient.store(status >= 500, Ordering::SeqCst);
+
+        log::debug!(
+            target: "http",
+            "<- {} {} ({} bytes)",
+            status,
+            redact::redact_with(final_url.as_str(), &self.context.secrets),
+            body.len()
+        );
+
+        if let (Some(tape), Some(request)) = (&self.context.tape, recorded_request) {
+            let mut tape = tape.lock().map_err(|_| anyhow!("cassette lock poisoned"))?;
+            tape.record(Interaction {
+                request,
+                response: RecordedResponse {
+                    status,
+                    url: final_url.to_string(),
+                    headers,
+                    body: body.clone(),
+                },
+            });
+        }
+
+        Ok(Exchange {
+            url: final_url,
+            body,
+        })
     }
 }
 
+fn header_pairs(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
 impl mlua::UserData for Agent {
     fn add_methods<'lua, T: mlua::UserDataMethods<'lua, Self>>(methods: &mut T) {
         methods.add_method("get", |_, agent, (url,): (String,)| {
-            let response = agent.client.get(url).send().unwrap().text().unwrap();
-            Ok(response)
+            let exchange = agent
+                .send(reqwest::Method::GET, &url, None)
+                .map_err(mlua::Error::external)?;
+            Ok(exchange.body)
         });
 
         methods.add_method("get_with", |_, agent, (url,): (String,)| {
-            let response = agent.client.get(url).send().unwrap();
-            let url = response.url();
-            let url = format!("{}://{}/", url.scheme(), url.host().unwrap());
-            let text = response.text().unwrap();
-            Ok((text, url))
+            let exchange = agent
+                .send(reqwest::Method::GET, &url, None)
+                .map_err(mlua::Error::external)?;
+            let host = exchange
+                .url
+                .host()
+                .ok_or_else(|| mlua::Error::external(anyhow!("response url has no host")))?;
+            let url = format!("{}://{}/", exchange.url.scheme(), host);
+            Ok((exchange.body, url))
         });
 
         methods.add_method("post", |_, agent, (url, form): (String, mlua::Table)| {
             let form: std::collections::HashMap<String, String> = form
                 .pairs::<String, String>()
-                .into_iter()
-                .map(|pair| {
-                    let (k, v) = pair.unwrap();
-                    (k, v)
-                })
-                .collect();
+                .collect::<mlua::Result<_>>()?;
 
-            let response = agent.client.post(url).form(&form).send().unwrap();
-            let response_text = response.text().unwrap();
+            let exchange = agent
+                .send(reqwest::Method::POST, &url, Some(&form))
+                .map_err(mlua::Error::external)?;
 
-            Ok(response_text)
+            Ok(exchange.body)
         });
 
         methods.add_method(
             "load_cookie",
             |_, agent, (url, cookies): (String, String)| {
-                let url: reqwest::Url = url.parse().unwrap();
+                let url: reqwest::Url = url.parse().map_err(mlua::Error::external)?;
 
                 let jar1 = agent.cookie_jar.clone();
                 for cookie in cookies.split(';').map(|x| x.trim()) {
@@ -284,3 +1588,204 @@ fn get_cookie_by_cowv2(url: String, re: String, title: String) -> Result<String>
         Err(anyhow::anyhow!("cowv2 exit with no cookie"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(username: &str, server: &str) -> UserInfo {
+        UserInfo {
+            username: username.to_owned(),
+            password: "secret".to_owned(),
+            strategy: "337".to_owned(),
+            server: server.to_owned(),
+            servers: Vec::new(),
+            nickname: None,
+            last_used: None,
+            tags: Vec::new(),
+            launcher: None,
+        }
+    }
+
+    fn temp_store() -> (tempfile::TempDir, StoreEngine) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = StoreEngine::create(dir.path().join(DATABASE_FILE).to_str().unwrap()).unwrap();
+        (dir, db)
+    }
+
+    fn is_duplicate_of(err: &anyhow::Error, owner: &uuid::Uuid) -> bool {
+        matches!(err.downcast_ref(), Some(DataError::Duplicate(uuid)) if uuid == owner)
+    }
+
+    /// Pretend `uuid` went to the trash `age` earlier than it did.
+    fn backdate(db: &StoreEngine, uuid: &uuid::Uuid, age: Duration) {
+        let write_txn = db.db.begin_write().unwrap();
+        {
+            let mut trash = write_txn.open_table(TRASH_TABLE).unwrap();
+            let key = uuid.to_string();
+            let json = trash.get(key.as_str()).unwrap().unwrap().value().to_owned();
+            let mut deleted: DeletedUser = serde_json::from_str(&json).unwrap();
+            deleted.deleted_at -= age.as_millis() as u64;
+            let json = serde_json::to_string(&deleted).unwrap();
+            trash.insert(key.as_str(), json.as_str()).unwrap();
+        }
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn restore_brings_back_a_deleted_account() {
+        let (_dir, mut db) = temp_store();
+        let uuid = uuid::Uuid::new_v4();
+        db.insert(&uuid, &user("Jogador", "10000")).unwrap();
+
+        db.remove(&uuid).unwrap();
+        assert!(db.get_user(&uuid).is_none());
+        assert_eq!(db.trashed().len(), 1);
+
+        let restored = db.restore(&uuid).unwrap();
+        assert_eq!(restored.username, "Jogador");
+        assert_eq!(db.get_user(&uuid).unwrap().server, "10000");
+        assert!(db.trashed().is_empty());
+        assert!(db.restore(&uuid).is_err());
+    }
+
+    #[test]
+    fn purge_respects_trash_days() {
+        let (_dir, mut db) = temp_store();
+        let (old, recent) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        db.insert(&old, &user("Antigo", "10000")).unwrap();
+        db.insert(&recent, &user("Recente", "10000")).unwrap();
+        db.remove(&old).unwrap();
+        db.remove(&recent).unwrap();
+        backdate(&db, &old, Duration::from_secs(31 * 24 * 60 * 60));
+        backdate(&db, &recent, Duration::from_secs(29 * 24 * 60 * 60));
+
+        db.purge_expired(&Settings { trash_days: 0, ..Settings::default() });
+        assert_eq!(db.trashed().len(), 2);
+
+        db.purge_expired(&Settings { trash_days: 30, ..Settings::default() });
+        let left: Vec<uuid::Uuid> = db.trashed().into_iter().map(|(uuid, _)| uuid).collect();
+        assert_eq!(left, [recent]);
+
+        assert_eq!(db.purge_trash(Duration::ZERO).unwrap(), 1);
+        assert!(db.trashed().is_empty());
+    }
+
+    #[test]
+    fn insert_rejects_a_login_taken_by_another_account() {
+        let (_dir, mut db) = temp_store();
+        let (owner, other) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        db.insert(&owner, &user("Jogador", "10000")).unwrap();
+
+        let err = db.insert(&other, &user(" jogador ", "10001")).unwrap_err();
+        assert!(is_duplicate_of(&err, &owner));
+        let mut elsewhere = user("Jogador", "10001");
+        elsewhere.strategy = "outra".to_owned();
+        db.insert(&other, &elsewhere).unwrap();
+
+        // Trocar o login libera o antigo
+        db.insert(&owner, &user("Outro", "10000")).unwrap();
+        db.insert(&uuid::Uuid::new_v4(), &user("Jogador", "10000")).unwrap();
+    }
+
+    #[test]
+    fn merge_moves_servers_tags_and_history() {
+        let (_dir, mut db) = temp_store();
+        let (keep, other) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        db.insert(&keep, &user("Jogador", "10000")).unwrap();
+        let mut second = user("Segundo", "10001");
+        second.tags.push("farm".to_owned());
+        db.insert(&other, &second).unwrap();
+        db.record_login(&keep, LoginRecord::new("10000", &LoginOutcome::Cancelled)).unwrap();
+        db.record_login(&other, LoginRecord::new("10001", &LoginOutcome::TimedOut)).unwrap();
+
+        let merged = db.merge(&keep, &[other, keep, other]).unwrap();
+        let servers: Vec<&str> = merged.servers.iter().map(|entry| entry.server.as_str()).collect();
+        assert_eq!(servers, ["10000", "10001"]);
+        assert_eq!(merged.tags, ["farm"]);
+        let mut history: Vec<String> = db.history(&keep).into_iter().map(|record| record.outcome).collect();
+        history.sort();
+        assert_eq!(history, ["cancelled", "timeout"]);
+        assert!(db.get_user(&other).is_none());
+        assert_eq!(db.trashed()[0].0, other);
+    }
+
+    #[test]
+    fn url_encode_escapes_everything_but_unreserved() {
+        assert_eq!(url_encode("p@ss w&rd=1+é"), "p%40ss%20w%26rd%3D1%2B%C3%A9");
+        assert_eq!(url_encode("Abc-_.~9"), "Abc-_.~9");
+
+        let line = format!("GET https://www.337.com/api.php?password={}", url_encode("p@ss w&rd"));
+        assert!(!redact::redact_secrets(&line, &["p@ss w&rd".to_owned()]).contains("p%40ss"));
+    }
+
+    #[test]
+    fn retry_policy_reads_script_metadata() {
+        let policy = RetryPolicy::for_script("-- Strategy: test\n-- Retries: 5\n-- Backoff: 0.5\n");
+        assert_eq!(policy.retries, 5);
+        assert_eq!(policy.backoff, Duration::from_millis(500));
+        assert_eq!(policy.delay(3), Duration::from_secs(2));
+
+        let policy = RetryPolicy::for_script("-- Retries: many\n-- Backoff: -1\n");
+        assert_eq!(policy.retries, RetryPolicy::default().retries);
+        assert_eq!(policy.backoff, RetryPolicy::default().backoff);
+    }
+
+    #[test]
+    fn retries_share_the_login_timeout() {
+        let script = "-- Retries: 10\n-- Backoff: 0.2\nfunction login() return agent():get(\"http://127.0.0.1:1/\") end\n";
+        let options = ExecuteOptions {
+            timeout: Some(Duration::from_secs(1)),
+            ..ExecuteOptions::default()
+        };
+        let started = Instant::now();
+        let mut retries = 0;
+        let outcome =
+            execute_login_with_retry(script, "", "", "", &options, &RetryPolicy::for_script(script), |_| retries += 1);
+        assert!(
+            matches!(outcome, LoginOutcome::Failed { transient: true, .. } | LoginOutcome::TimedOut),
+            "{:?}",
+            outcome
+        );
+        assert!((1..10).contains(&retries), "{} retries", retries);
+        assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn migration_keeps_legacy_duplicates_editable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        let (first, second) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        {
+            // Um banco de antes do indice: uma conta por servidor
+            let db = redb::Database::create(&path).unwrap();
+            let write_txn = db.begin_write().unwrap();
+            {
+                let mut table = write_txn.open_table(TABLE).unwrap();
+                for (uuid, server) in [(first, "10000"), (second, "10001")] {
+                    let json = serde_json::to_string(&user("Jogador", server)).unwrap();
+                    table.insert(uuid.to_string().as_str(), json.as_str()).unwrap();
+                }
+            }
+            write_txn.commit().unwrap();
+        }
+
+        let mut db = StoreEngine::create(path.to_str().unwrap()).unwrap();
+        assert!(db.users().iter().all(|(_, user)| user.servers.len() == 1));
+        assert_eq!(db.duplicates().len(), 1);
+
+        for uuid in [first, second] {
+            let mut edited = db.get_user(&uuid).unwrap();
+            edited.tags.push("farm".to_owned());
+            db.insert(&uuid, &edited).unwrap();
+        }
+        let err = db.insert(&uuid::Uuid::new_v4(), &user("jogador", "10005")).unwrap_err();
+        assert!(is_duplicate_of(&err, &first) || is_duplicate_of(&err, &second));
+
+        db.merge(&second, &[first]).unwrap();
+        assert!(db.duplicates().is_empty());
+        assert_eq!(db.get_user(&second).unwrap().servers.len(), 2);
+        let err = db.insert(&uuid::Uuid::new_v4(), &user("jogador", "10005")).unwrap_err();
+        assert!(is_duplicate_of(&err, &second));
+    }
+}
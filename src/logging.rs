@@ -0,0 +1,259 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+
+use crate::{now_millis, redact::redact};
+
+const LOG_FILE: &str = "ddtank-rs.log";
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+const KEPT_FILES: usize = 5;
+const KEPT_ENTRIES: usize = 2000;
+
+thread_local! {
+    static ACCOUNT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// One log line as kept in memory for the log viewer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub level: String,
+    pub target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    pub message: String,
+}
+
+/// How the process-wide logger is set up.
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    /// `info`, or per-target like `info,http=debug,strategy=trace`.
+    /// `DDTANK_LOG` overrides it when set.
+    pub filter: String,
+    /// Directory for rotating log files; `None` logs to stderr only.
+    pub dir: Option<PathBuf>,
+    pub stderr: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_owned(),
+            dir: None,
+            stderr: true,
+        }
+    }
+}
+
+/// Every record logged on this thread until the guard is dropped is
+/// attributed to `account`.
+pub struct AccountScope {
+    previous: Option<String>,
+}
+
+impl AccountScope {
+    pub fn enter(account: &str) -> Self {
+        let previous = ACCOUNT.with(|a| a.replace(Some(account.to_owned())));
+        Self { previous }
+    }
+}
+
+impl Drop for AccountScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        ACCOUNT.with(|a| *a.borrow_mut() = previous);
+    }
+}
+
+struct Filter {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    fn parse(spec: &str) -> Self {
+        let mut filter = Filter {
+            default: LevelFilter::Info,
+            targets: Vec::new(),
+        };
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some((target, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        filter.targets.push((target.trim().to_owned(), level));
+                    }
+                }
+                None => {
+                    if let Ok(level) = part.parse() {
+                        filter.default = level;
+                    }
+                }
+            }
+        }
+        filter
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_FILE);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.size + line.len() as u64 > MAX_FILE_SIZE {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// `ddtank-rs.log` becomes `ddtank-rs.log.1`, `.1` becomes `.2`, and so on.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let numbered = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        let _ = fs::remove_file(numbered(KEPT_FILES));
+        for n in (1..KEPT_FILES).rev() {
+            let _ = fs::rename(numbered(n), numbered(n + 1));
+        }
+        fs::rename(&self.path, numbered(1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+struct Logger {
+    filter: Filter,
+    stderr: bool,
+    file: Option<Mutex<RotatingFile>>,
+}
+
+static RECENT: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let entry = LogEntry {
+            timestamp: now_millis(),
+            level: record.level().to_string(),
+            target: record.target().to_owned(),
+            account: ACCOUNT.with(|a| a.borrow().clone()),
+            message: redact(&record.args().to_string()),
+        };
+
+        let account = entry
+            .account
+            .as_ref()
+            .map(|a| format!(" <{}>", a))
+            .unwrap_or_default();
+        if self.stderr {
+            eprintln!("[{} {}]{} {}", entry.level, entry.target, account, entry.message);
+        }
+        if let Some(file) = &self.file {
+            let line = format!(
+                "{} [{} {}]{} {}\n",
+                time::OffsetDateTime::now_utc(),
+                entry.level,
+                entry.target,
+                account,
+                entry.message
+            );
+            if let Ok(mut file) = file.lock() {
+                let _ = file.write_line(&line);
+            }
+        }
+
+        if let Ok(mut recent) = RECENT.lock() {
+            if recent.len() == KEPT_ENTRIES {
+                recent.pop_front();
+            }
+            recent.push_back(entry);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.file.flush();
+            }
+        }
+    }
+}
+
+/// Install the process-wide logger.
+pub fn init(config: LogConfig) {
+    let spec = std::env::var("DDTANK_LOG").unwrap_or(config.filter);
+    let filter = Filter::parse(&spec);
+    let max_level = filter.max_level();
+
+    let file = config.dir.as_deref().and_then(|dir| match RotatingFile::open(dir) {
+        Ok(file) => Some(Mutex::new(file)),
+        Err(e) => {
+            eprintln!("failed to open log file in {}: {}", dir.display(), e);
+            None
+        }
+    });
+
+    let logger = Logger {
+        filter,
+        stderr: config.stderr,
+        file,
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Most recent entries at or above `level`, optionally only for `account`, oldest first.
+pub fn recent(level: Level, account: Option<&str>) -> Vec<LogEntry> {
+    let recent = match RECENT.lock() {
+        Ok(recent) => recent,
+        Err(_) => return Vec::new(),
+    };
+    recent
+        .iter()
+        .filter(|entry| entry.level.parse::<Level>().map_or(true, |l| l <= level))
+        .filter(|entry| account.is_none_or(|a| entry.account.as_deref() == Some(a)))
+        .cloned()
+        .collect()
+}
@@ -1,244 +1,1020 @@
 // Inspector habilitado - remover windows_subsystem para permitir debug
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use ddtank_rs::{StoreEngine, UserInfo};
+use ddtank_rs::ipc;
+use ddtank_rs::jobs::WorkerPool;
+use ddtank_rs::logging::{self, AccountScope, LogConfig};
+use ddtank_rs::settings::Overrides;
+use ddtank_rs::tools::ToolProcesses;
+use ddtank_rs::{
+    CancelToken, DataError, ExecuteOptions, GameProcess, Location, LoginOutcome, LoginRecord, RetryPolicy, Settings,
+    StoreEngine, ToolInfo, UserInfo,
+};
 
+use sciter::dom::event::{EventReason, BEHAVIOR_EVENTS, PHASE_MASK};
+use sciter::dom::{Element, HELEMENT};
 use sciter::{make_args, Value};
 use sciter::window::{Builder, Options};
-use std::thread;
+use std::collections::HashMap;
+use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
-use std::process::Child;
+use std::time::Instant;
+
+/// Posted to the root element when a worker has results for the UI thread.
+const JOBS_DONE_EVENT: BEHAVIOR_EVENTS = BEHAVIOR_EVENTS::FIRST_APPLICATION_EVENT_CODE;
+
+/// A login still running on the worker pool. Only touched on the UI thread.
+struct PendingLogin {
+    /// Account whose `last_used` and history are updated once it ends.
+    account: uuid::Uuid,
+    /// Server ID logged into.
+    server: String,
+    cancel: CancelToken,
+    done_callback: Value,
+    progress_callback: Value,
+    /// Handed to the game opened when the login succeeds.
+    exit_callback: Value,
+}
+
+/// What a worker or game watcher reports back to the UI thread.
+enum WorkerEvent {
+    /// An attempt failed and another one follows after `delay_secs`.
+    Retrying {
+        handle: String,
+        attempt: u32,
+        max_attempts: u32,
+        delay_secs: f64,
+        message: String,
+    },
+    Done {
+        handle: String,
+        outcome: LoginOutcome,
+    },
+    /// A game started by `start_game` exited.
+    GameExited {
+        account_id: String,
+        pid: u32,
+        code: Option<i32>,
+    },
+}
+
+/// A game client started for an account. Only touched on the UI thread.
+struct RunningGame {
+    process: GameProcess,
+    on_exit: Value,
+}
+
+/// Wakes the UI thread up from a worker. `None` once the window is gone.
+#[derive(Clone, Default)]
+struct UiNotifier {
+    root: Arc<Mutex<Option<RootElement>>>,
+}
+
+struct RootElement(HELEMENT);
+
+// SAFETY: workers only use the handle to post an event, which Sciter queues
+// for the UI thread, and only while the document is still open.
+unsafe impl Send for RootElement {}
+
+impl UiNotifier {
+    fn attach(&self, root: HELEMENT) {
+        if let Ok(mut current) = self.root.lock() {
+            *current = Some(RootElement(root));
+        }
+    }
+
+    fn detach(&self) {
+        if let Ok(mut current) = self.root.lock() {
+            *current = None;
+        }
+    }
+
+    fn notify(&self) {
+        // Holding the lock while posting keeps `detach` from racing us.
+        let Ok(root) = self.root.lock() else { return };
+        match root.as_ref() {
+            Some(root) => {
+                if let Err(e) = Element::from(root.0).post_event(JOBS_DONE_EVENT, None, None) {
+                    log::warn!("Falha ao notificar a interface: {:?}", e);
+                }
+            }
+            None => log::debug!("Janela fechada, resultado descartado"),
+        }
+    }
+}
+
+/// Why a script call failed, the `code` of its [`Reply`].
+#[derive(Clone, Copy, Debug)]
+enum ErrorCode {
+    /// Arguments the call can't use: a malformed ID, an incomplete form.
+    Validation,
+    /// The call would make a second copy of something that must be unique.
+    Duplicate,
+    NotFound,
+    /// Reading or writing the database or the settings file failed.
+    Storage,
+    /// The account's strategy is missing.
+    Strategy,
+    /// A game or tool process could not be started, focused or stopped.
+    Process,
+    /// The master password did not match.
+    Denied,
+}
+
+impl ErrorCode {
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Validation => "validation",
+            ErrorCode::Duplicate => "duplicate",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Storage => "storage",
+            ErrorCode::Strategy => "strategy",
+            ErrorCode::Process => "process",
+            ErrorCode::Denied => "denied",
+        }
+    }
+
+    /// The code of a library error: from its [`DataError`], else `fallback`.
+    fn of(err: &anyhow::Error, fallback: ErrorCode) -> ErrorCode {
+        match err.downcast_ref::<DataError>() {
+            Some(DataError::Invalid(_)) => ErrorCode::Validation,
+            Some(DataError::AccountNotFound(_) | DataError::NotInTrash(_)) => ErrorCode::NotFound,
+            Some(DataError::Duplicate(_)) => ErrorCode::Duplicate,
+            Some(DataError::UnknownStrategy(_)) => ErrorCode::Strategy,
+            None => fallback,
+        }
+    }
+}
+
+/// What every script call returns: `{ ok: true, data }` or
+/// `{ ok: false, code, message }`. The UI words its errors after `code`;
+/// `message` is the redacted detail.
+struct Reply(Value);
+
+impl Reply {
+    fn ok(data: impl Into<Value>) -> Self {
+        let mut reply = Value::new();
+        reply.set_item("ok", true);
+        reply.set_item("data", data.into());
+        Reply(reply)
+    }
+
+    /// Success with nothing to return.
+    fn done() -> Self {
+        Self::ok(Value::null())
+    }
+
+    fn error(code: ErrorCode, message: impl std::fmt::Display) -> Self {
+        let mut reply = Value::new();
+        reply.set_item("ok", false);
+        reply.set_item("code", code.as_str());
+        reply.set_item("message", ddtank_rs::redact::redact(&message.to_string()));
+        Reply(reply)
+    }
+
+    /// Data built by [`ipc`], which only fails on a type serde can't write.
+    fn data(data: anyhow::Result<serde_json::Value>) -> Self {
+        match data {
+            Ok(data) => Self::ok(json_value(data)),
+            Err(e) => {
+                log::error!("Erro ao converter dados para a interface: {:#}", e);
+                Self::failed(&e, ErrorCode::Storage)
+            }
+        }
+    }
+
+    /// A library error, with `fallback` as its code unless it is a [`DataError`].
+    fn failed(err: &anyhow::Error, fallback: ErrorCode) -> Self {
+        Self::error(ErrorCode::of(err, fallback), format!("{:#}", err))
+    }
+}
+
+impl From<Reply> for Value {
+    fn from(reply: Reply) -> Self {
+        reply.0
+    }
+}
+
+/// The Sciter form of a JSON value. Integers that don't fit an `i32`, like
+/// timestamps, become floats as JavaScript numbers are.
+fn json_value(json: serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::null(),
+        serde_json::Value::Bool(b) => Value::from(b),
+        serde_json::Value::Number(n) => match n.as_i64().and_then(|n| i32::try_from(n).ok()) {
+            Some(n) => Value::from(n),
+            None => Value::from(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::from(s),
+        serde_json::Value::Array(items) => {
+            let mut array = Value::array(0);
+            for item in items {
+                array.push(json_value(item));
+            }
+            array
+        }
+        serde_json::Value::Object(fields) => {
+            let mut object = Value::new();
+            for (key, value) in fields {
+                object.set_item(key, json_value(value));
+            }
+            object
+        }
+    }
+}
+
+/// An ID sent by the UI, or the `validation` reply to return.
+fn parse_uuid(id: &str) -> Result<uuid::Uuid, Reply> {
+    uuid::Uuid::parse_str(id).map_err(|e| {
+        log::error!("Error parsing UUID: {:?}", e);
+        Reply::error(ErrorCode::Validation, format!("invalid id {:?}: {}", id, e))
+    })
+}
+
+/// A form sent by the UI, or the `validation` reply to return.
+fn parse_value<T: serde::de::DeserializeOwned>(value: &Value, what: &str) -> Result<T, Reply> {
+    let mut value = value.clone();
+    value.isolate();
+    sciter_serde::from_value(&value).map_err(|e| {
+        log::error!("Error deserializing {}: {:?}", what, e);
+        Reply::error(ErrorCode::Validation, format!("invalid {}: {:?}", what, e))
+    })
+}
 
 struct DDTankHandler {
+    location: Location,
+    settings: Settings,
     strategy: ddtank_rs::Strategy,
     db: StoreEngine,
-    child_processes: Arc<Mutex<Vec<Child>>>,
+    /// No database existed before this start, the UI shows the onboarding.
+    first_run: bool,
+    /// Tools started from the toolbar, with the ID of their tool entry.
+    child_processes: Arc<Mutex<ToolProcesses>>,
+    pool: WorkerPool,
+    logins: HashMap<String, PendingLogin>,
+    /// Running game clients, at most one per account ID.
+    games: HashMap<String, RunningGame>,
+    results_tx: mpsc::Sender<WorkerEvent>,
+    results_rx: mpsc::Receiver<WorkerEvent>,
+    notifier: UiNotifier,
 }
 
 impl DDTankHandler {
-    fn new() -> Self {
-        let exe_dir = std::env::current_exe()
-            .ok()
-            .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-            .unwrap_or_else(|| std::env::current_dir().unwrap());
-        
-        let scripts_pattern = exe_dir.join("scripts").join("*.lua");
-        let db_path = exe_dir.join("userdata.redb");
-        
-        let strategy = ddtank_rs::Strategy::new(scripts_pattern.to_str().unwrap());
-        let db = StoreEngine::create(db_path.to_str().unwrap()).unwrap();
+    fn new(location: Location, settings: Settings) -> Self {
+        let db_path = location.database();
+
+        let strategy = settings.strategy(&location);
+        let mut db = StoreEngine::create(db_path.to_str().unwrap()).unwrap();
+        db.purge_expired(&settings);
+        let (results_tx, results_rx) = mpsc::channel();
+        let pool = WorkerPool::new(settings.workers);
+        let first_run = db.is_new();
         Self { 
+            location,
+            settings,
             strategy, 
             db,
-            child_processes: Arc::new(Mutex::new(Vec::new())),
+            first_run,
+            child_processes: Arc::new(Mutex::new(ToolProcesses::default())),
+            pool,
+            logins: HashMap::new(),
+            games: HashMap::new(),
+            results_tx,
+            results_rx,
+            notifier: UiNotifier::default(),
         }
     }
 
-    fn login(
+    /// Queue a login for a stored account on the worker pool and reply with
+    /// its handle for `cancel_login`. `server` is one of the account's server
+    /// IDs, empty for its default server. The credentials never leave Rust,
+    /// and the account's `last_used` and login history are updated when it
+    /// ends.
+    /// When the strategy returns a game URL the game is opened right here,
+    /// so the URL never reaches the UI either.
+    /// `done_callback(message, outcome, launch)` is called exactly once on the
+    /// UI thread, with `outcome` one of `ok`, `error`, `cancelled` or `timeout`
+    /// and `launch` the reply of opening the game, null when none was opened.
+    /// `progress_callback(attempt, max_attempts, delay_secs, message)` is called
+    /// for every failed attempt that will be retried. `exit_callback(code)` is
+    /// called once the opened game exits, see
+    /// [`DDTankHandler::start_game`]. A missing account or strategy or server,
+    /// or an account already in game, is an error reply instead, and no
+    /// callback is called.
+    fn login_account(
         &mut self,
-        strategy: String,
-        username: String,
-        password: String,
+        account_id: String,
         server: String,
         done_callback: Value,
-    ) -> bool {
-        let script = self.strategy.get(&strategy).unwrap();
-        thread::spawn(move || {
-            let result = ddtank_rs::execute_strategy(&script, &username, &password, &server);
-            let result = match result {
-                Ok(url) => {
-                    println!("Login bem-sucedido! URL retornada: {}", url);
-                    url
-                },
-                Err(err) => {
-                    println!("Erro no login: {}", err);
-                    format!("error{}", err.to_string())
-                },
+        progress_callback: Value,
+        exit_callback: Value,
+    ) -> Reply {
+        let account = match parse_uuid(&account_id) {
+            Ok(u) => u,
+            Err(reply) => return reply,
+        };
+        // Antes de logar: o jogo so seria recusado depois do login inteiro
+        if let Some(reply) = self.in_game(&account_id) {
+            return reply;
+        }
+        let Some(user) = self.db.get_user(&account) else {
+            log::error!("Conta {} não encontrada", account_id);
+            return Reply::error(ErrorCode::NotFound, DataError::AccountNotFound(account));
+        };
+        let Some(server) = user.server_entry(&server).map(|entry| entry.server.clone()) else {
+            log::error!("Conta {} não tem o servidor {}", account_id, server);
+            return Reply::error(ErrorCode::Validation, format!("account has no server {}", server));
+        };
+        let UserInfo { strategy, username, password, .. } = user;
+
+        let script = match self.strategy.get(&strategy) {
+            Ok(script) => script,
+            Err(err) => {
+                log::error!("Estratégia {} indisponível: {}", strategy, err);
+                let outcome = LoginOutcome::Failed {
+                    message: err.to_string(),
+                    transient: false,
+                };
+                if let Err(e) = self.db.record_login(&account, LoginRecord::new(&server, &outcome)) {
+                    log::warn!("Erro ao registrar o login da conta {}: {:#}", account, e);
+                }
+                return Reply::failed(&err, ErrorCode::Strategy);
+            }
+        };
+
+        let handle = uuid::Uuid::new_v4().to_string();
+        let cancel = CancelToken::new();
+        self.logins.insert(handle.clone(), PendingLogin {
+            account,
+            server: server.clone(),
+            cancel: cancel.clone(),
+            done_callback,
+            progress_callback,
+            exit_callback,
+        });
+        let options = ExecuteOptions {
+            cancel: Some(cancel),
+            timeout: Some(ddtank_rs::login_timeout(&script, self.settings.login_timeout())),
+            user_agent: Some(self.settings.user_agent.clone()),
+            ..ExecuteOptions::default()
+        };
+        let policy = RetryPolicy::for_script(&script);
+
+        let results = self.results_tx.clone();
+        let notifier = self.notifier.clone();
+        let job_handle = handle.clone();
+        // O tempo limite conta desde o pedido, inclusive a espera na fila
+        let submitted = Instant::now();
+        let queued = self.pool.execute(move || {
+            let _scope = AccountScope::enter(&username);
+            let send = |event: WorkerEvent| {
+                if results.send(event).is_ok() {
+                    notifier.notify();
+                }
+            };
+            let options = ExecuteOptions {
+                timeout: options.timeout.map(|timeout| timeout.saturating_sub(submitted.elapsed())),
+                ..options
             };
-            done_callback.call(None, &make_args!(result), None).unwrap();
+            let outcome = if options.timeout.is_some_and(|timeout| timeout.is_zero()) {
+                LoginOutcome::TimedOut
+            } else {
+                ddtank_rs::execute_login_with_retry(
+                    &script,
+                    &username,
+                    &password,
+                    &server,
+                    &options,
+                    &policy,
+                    |retry| {
+                        send(WorkerEvent::Retrying {
+                            handle: job_handle.clone(),
+                            attempt: retry.attempt,
+                            max_attempts: retry.max_attempts,
+                            delay_secs: retry.delay.as_secs_f64(),
+                            message: retry.outcome.message().to_owned(),
+                        })
+                    },
+                )
+            };
+            match &outcome {
+                LoginOutcome::Success(url) => log::info!("Login bem-sucedido! URL retornada: {}", url),
+                LoginOutcome::Failed { message, .. } => log::warn!("Erro no login: {}", message),
+                LoginOutcome::Cancelled => log::info!("Login cancelado"),
+                LoginOutcome::TimedOut => log::warn!("Tempo limite do login esgotado"),
+            }
+            send(WorkerEvent::Done {
+                handle: job_handle,
+                outcome,
+            });
         });
-        true
+        if let Err(err) = queued {
+            log::error!("Não foi possível iniciar o login: {}", err);
+            self.finish_login(&handle, LoginOutcome::Failed {
+                message: err.to_string(),
+                transient: false,
+            });
+        }
+        Reply::ok(handle)
     }
 
-    fn get_all_strategy(&self) -> Value {
+    /// Stop a running login. The UI is told right away; the worker winds
+    /// down at its next Lua instruction or HTTP request.
+    fn cancel_login(&mut self, handle: String) -> Reply {
+        match self.logins.get(&handle) {
+            Some(pending) => {
+                pending.cancel.cancel();
+                self.finish_login(&handle, LoginOutcome::Cancelled);
+                Reply::done()
+            }
+            None => Reply::error(ErrorCode::NotFound, format!("login {} already finished", handle)),
+        }
+    }
+
+    /// Report a login's outcome to the UI, unless it was already reported.
+    fn finish_login(&mut self, handle: &str, outcome: LoginOutcome) {
+        let Some(pending) = self.logins.remove(handle) else { return };
+        if let Err(e) = self.db.record_login(&pending.account, LoginRecord::new(&pending.server, &outcome)) {
+            log::warn!("Erro ao registrar o login da conta {}: {:#}", pending.account, e);
+        }
+        let (message, launch) = match &outcome {
+            LoginOutcome::Success(url) if ddtank_rs::is_game_url(url) => {
+                let launch = self.start_game(&pending.account, url, pending.exit_callback);
+                (String::new(), Value::from(launch))
+            }
+            LoginOutcome::Failed { message, .. } => (ddtank_rs::redact::redact(message), Value::null()),
+            _ => (outcome.message().to_owned(), Value::null()),
+        };
+        if let Err(e) = pending.done_callback.call(None, &make_args!(message, outcome.kind(), launch), None) {
+            log::error!("Erro ao notificar a interface: {:?}", e);
+        }
+    }
+
+    /// Hand everything the workers reported to the UI. Runs on the UI thread.
+    fn deliver_results(&mut self) {
+        while let Ok(event) = self.results_rx.try_recv() {
+            match event {
+                WorkerEvent::Retrying {
+                    handle,
+                    attempt,
+                    max_attempts,
+                    delay_secs,
+                    message,
+                } => {
+                    // Gone when the login was cancelled in the meantime.
+                    let Some(pending) = self.logins.get(&handle) else { continue };
+                    let message = ddtank_rs::redact::redact(&message);
+                    let args = make_args!(attempt as i32, max_attempts as i32, delay_secs, message);
+                    if let Err(e) = pending.progress_callback.call(None, &args, None) {
+                        log::error!("Erro ao notificar a interface: {:?}", e);
+                    }
+                }
+                WorkerEvent::Done { handle, outcome } => self.finish_login(&handle, outcome),
+                WorkerEvent::GameExited { account_id, pid, code } => {
+                    // A newer launch of the same account is not this one.
+                    if self.games.get(&account_id).is_none_or(|game| game.process.id() != pid) {
+                        continue;
+                    }
+                    let Some(game) = self.games.remove(&account_id) else { continue };
+                    let code = code.map(Value::from).unwrap_or_else(Value::null);
+                    if let Err(e) = game.on_exit.call(None, &make_args!(code), None) {
+                        log::error!("Erro ao notificar a interface: {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_logs(&self, level: String, account: String) -> Reply {
+        let level = level.parse().unwrap_or(log::Level::Trace);
+        let account = Some(account.as_str()).filter(|a| !a.is_empty());
+        Reply::data(ipc::logs(&logging::recent(level, account)))
+    }
+
+    fn get_all_strategy(&self) -> Reply {
         let strategy_list = self.strategy.list();
-        Value::from_iter(strategy_list)
-    }
-
-    fn play_flash(&self, url: String) -> anyhow::Result<()> {
-        println!("Tentando abrir: {}", url);
-        
-        // Se for URL do 337.com, usar comando do Windows para abrir
-        if url.contains("337.com") || url.starts_with("http://s") {
-            println!("Detectada URL do 337.com - abrindo com navegador padrão");
-            std::process::Command::new("cmd")
-                .args(&["/c", "start", "", &url])
-                .spawn()?;
-            return Ok(());
-        }
-        
-        // Para URLs SWF, usar flashplayer
-        let flashplayer = if cfg!(target_os = "windows") {
-            "./flashplayer_sa.exe"
-        } else {
-            "./flashplayer"
+        Reply::ok(Value::from_iter(strategy_list))
+    }
+
+    /// Saved settings, without environment overrides, plus where they and
+    /// the data live.
+    fn settings_get(&self) -> Reply {
+        let settings = Settings::load(&self.location).unwrap_or_else(|e| {
+            log::warn!("Configurações ignoradas: {:#}", e);
+            Settings::default()
+        });
+        Reply::data(ipc::settings(&settings, &self.location))
+    }
+
+    /// Save the settings and apply them right away: scripts are reloaded
+    /// and a new worker pool takes the next logins.
+    fn settings_save(&mut self, settings: Value) -> Reply {
+        let mut settings: Settings = match parse_value(&settings, "settings") {
+            Ok(s) => s,
+            Err(reply) => return reply,
         };
+        // So muda por `set_master_password`
+        settings.master_password = self.settings.master_password.clone();
+        if let Err(e) = settings.save(&self.location) {
+            log::error!("Erro ao salvar configurações: {:#}", e);
+            return Reply::failed(&e, ErrorCode::Storage);
+        }
+        log::info!("Configurações salvas em {}", self.location.settings_file().display());
 
-        println!("Abrindo com flashplayer: {}", flashplayer);
-        std::process::Command::new(flashplayer).arg(url).output()?;
-        Ok(())
+        let settings = settings.with_env();
+        if settings.workers != self.settings.workers {
+            self.pool = WorkerPool::new(settings.workers);
+        }
+        self.strategy = settings.strategy(&self.location);
+        self.settings = settings;
+        Reply::done()
     }
 
-    fn open_reguinha(&self) -> bool {
-        let exe_dir = std::env::current_exe()
-            .ok()
-            .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-            .unwrap_or_else(|| std::env::current_dir().unwrap());
-        
-        let reguinha_path = exe_dir.join("reguinha.exe");
-        
-        match std::process::Command::new(&reguinha_path).spawn() {
+    /// Open the game for an account with the URL its login returned, using
+    /// the account's launcher and without blocking. Replies `duplicate` when
+    /// the account already has a game open. `on_exit(code)` is called on the
+    /// UI thread once the launched process exits; `code` is null when it was
+    /// killed. Games handed by the system launcher to a handler that does not
+    /// wait for the client (xdg-open) are not tracked and never show as in game.
+    fn start_game(&mut self, account: &uuid::Uuid, url: &str, on_exit: Value) -> Reply {
+        let account_id = account.to_string();
+        if let Some(reply) = self.in_game(&account_id) {
+            return reply;
+        }
+
+        let launcher = self.db.get_user(account).and_then(|user| {
+            let script = self.strategy.get(&user.strategy).unwrap_or_default();
+            ddtank_rs::launcher_spec(&user, &script)
+        });
+
+        let game = match ddtank_rs::launch_game(url, launcher.as_deref(), &self.settings) {
+            Ok(game) => game,
+            Err(e) => {
+                log::error!("Erro ao abrir o jogo: {:#}", e);
+                return Reply::failed(&e, ErrorCode::Process);
+            }
+        };
+
+        if !game.is_tracked() {
+            log::info!("Jogo da conta {} aberto pelo sistema, sem acompanhamento", account_id);
+            self.start_game_tools();
+            return Reply::done();
+        }
+
+        let results = self.results_tx.clone();
+        let notifier = self.notifier.clone();
+        let pid = game.id();
+        let watched_account = account_id.clone();
+        game.watch(move |code| {
+            let event = WorkerEvent::GameExited {
+                account_id: watched_account,
+                pid,
+                code,
+            };
+            if results.send(event).is_ok() {
+                notifier.notify();
+            }
+        });
+        self.games.insert(account_id, RunningGame {
+            process: game,
+            on_exit,
+        });
+        self.start_game_tools();
+        Reply::done()
+    }
+
+    /// A `duplicate` reply when the account already has a tracked game open.
+    fn in_game(&self, account_id: &str) -> Option<Reply> {
+        let game = self.games.get(account_id)?;
+        log::warn!("Conta {} já está em jogo (processo {})", account_id, game.process.id());
+        Some(Reply::error(
+            ErrorCode::Duplicate,
+            format!("account {} is already in game (process {})", account_id, game.process.id()),
+        ))
+    }
+
+    /// Running games by account ID: `{ pid, started_at, launcher }`.
+    fn game_status(&self) -> Reply {
+        Reply::data(
+            self.games
+                .iter()
+                .map(|(account_id, game)| {
+                    let game = ipc::Game {
+                        pid: game.process.id(),
+                        started_at: game.process.started_at(),
+                        launcher: game.process.launcher.to_owned(),
+                    };
+                    Ok((account_id.clone(), ipc::game(&game)?))
+                })
+                .collect(),
+        )
+    }
+
+    fn focus_game(&self, account_id: String) -> Reply {
+        let Some(game) = self.games.get(&account_id) else {
+            return Reply::error(ErrorCode::NotFound, format!("account {} is not in game", account_id));
+        };
+        match game.process.focus() {
+            Ok(()) => Reply::done(),
+            Err(e) => {
+                log::warn!("Não foi possível focar o jogo: {:#}", e);
+                Reply::failed(&e, ErrorCode::Process)
+            }
+        }
+    }
+
+    /// Kill an account's game. Its `on_exit` callback still fires once the
+    /// watcher sees the process gone.
+    fn kill_game(&self, account_id: String) -> Reply {
+        let Some(game) = self.games.get(&account_id) else {
+            return Reply::error(ErrorCode::NotFound, format!("account {} is not in game", account_id));
+        };
+        match game.process.kill() {
+            Ok(()) => {
+                log::info!("Jogo da conta {} encerrado (processo {})", account_id, game.process.id());
+                Reply::done()
+            }
+            Err(e) => {
+                log::error!("Erro ao encerrar o jogo: {:#}", e);
+                Reply::failed(&e, ErrorCode::Process)
+            }
+        }
+    }
+
+    /// Start a configured tool and track it so it is closed with the launcher.
+    fn open_tool(&self, tool_id: String) -> Reply {
+        let uuid = match parse_uuid(&tool_id) {
+            Ok(u) => u,
+            Err(reply) => return reply,
+        };
+        let Some(tool) = self.db.get_tool(&uuid) else {
+            log::error!("Ferramenta não encontrada: {}", tool_id);
+            return Reply::error(ErrorCode::NotFound, format!("tool {} does not exist", tool_id));
+        };
+        match self.spawn_tool(&tool_id, &tool) {
+            Ok(()) => Reply::done(),
+            Err(e) => Reply::failed(&e, ErrorCode::Process),
+        }
+    }
+
+    fn spawn_tool(&self, tool_id: &str, tool: &ToolInfo) -> anyhow::Result<()> {
+        match tool.spawn() {
             Ok(child) => {
-                println!("Reguinha aberto com sucesso! PID: {}", child.id());
+                log::info!("{} aberto com sucesso! PID: {}", tool.name, child.id());
                 if let Ok(mut processes) = self.child_processes.lock() {
-                    processes.push(child);
+                    processes.push(tool_id, tool, child);
                 }
-                true
-            },
+                Ok(())
+            }
             Err(e) => {
-                eprintln!("Erro ao abrir reguinha.exe: {}", e);
-                false
+                log::error!("Erro ao abrir {}: {:#}", tool.name, e);
+                Err(e)
             }
         }
     }
 
-    fn database_get(&self, user_id: String) -> Value {
-        let uuid = match uuid::Uuid::parse_str(&user_id) {
+    /// Whether a tool started by us is still running.
+    fn is_tool_running(&self, tool_id: &str) -> bool {
+        let Ok(mut processes) = self.child_processes.lock() else { return false };
+        processes.is_running(tool_id)
+    }
+
+    /// Start every `start_with_game` tool that is not already running.
+    fn start_game_tools(&self) {
+        for (uuid, tool) in self.db.tools() {
+            let tool_id = uuid.to_string();
+            if tool.start_with_game && !self.is_tool_running(&tool_id) {
+                // Ja registrado no log, o jogo abre mesmo assim
+                let _ = self.spawn_tool(&tool_id, &tool);
+            }
+        }
+    }
+
+    fn tools_list(&self) -> Reply {
+        let mut tools = self.db.tools();
+        tools.sort_by_key(|(_, tool)| tool.name.to_lowercase());
+        Reply::data(
+            tools
+                .iter()
+                .map(|(uuid, tool)| ipc::tool(uuid, tool, self.is_tool_running(&uuid.to_string())))
+                .collect(),
+        )
+    }
+
+    fn tool_add(&mut self, tool: Value) -> Reply {
+        self.save_tool(uuid::Uuid::new_v4(), &tool)
+    }
+
+    fn tool_replace(&mut self, tool_id: String, tool: Value) -> Reply {
+        let uuid = match parse_uuid(&tool_id) {
             Ok(u) => u,
+            Err(reply) => return reply,
+        };
+        self.save_tool(uuid, &tool)
+    }
+
+    /// Store a tool, replying with its ID.
+    fn save_tool(&mut self, uuid: uuid::Uuid, tool: &Value) -> Reply {
+        let tool: ToolInfo = match parse_value(tool, "tool") {
+            Ok(t) => t,
+            Err(reply) => return reply,
+        };
+        match self.db.insert_tool(&uuid, &tool) {
+            Ok(_) => Reply::ok(uuid.to_string()),
             Err(e) => {
-                eprintln!("Error parsing UUID: {:?}", e);
-                return Value::new();
+                log::error!("Error saving tool: {:?}", e);
+                Reply::failed(&e, ErrorCode::Storage)
             }
+        }
+    }
+
+    fn tool_delete(&mut self, tool_id: String) -> Reply {
+        let uuid = match parse_uuid(&tool_id) {
+            Ok(u) => u,
+            Err(reply) => return reply,
         };
-        
-        let user = match self.db.get_user(&uuid) {
-            Some(u) => u,
-            None => {
-                eprintln!("User not found: {}", user_id);
-                return Value::new();
+        match self.db.remove_tool(&uuid) {
+            Ok(_) => Reply::done(),
+            Err(e) => {
+                log::error!("Error deleting tool: {:?}", e);
+                Reply::failed(&e, ErrorCode::Storage)
             }
-        };
-        
-        // Manually construct Value
-        let mut user_obj = Value::new();
-        user_obj.set_item("username", user.username);
-        user_obj.set_item("password", user.password);
-        user_obj.set_item("strategy", user.strategy);
-        user_obj.set_item("server", user.server);
-        if let Some(nickname) = user.nickname {
-            user_obj.set_item("nickname", nickname);
-        }
-        if let Some(last_used) = user.last_used {
-            user_obj.set_item("last_used", last_used as f64);
-        }
-        
-        user_obj
-    }
-
-    fn database_get_all(&self) -> Value {
-        let mut users_vec: Vec<(String, UserInfo)> = self
-            .db
-            .users()
-            .into_iter()
-            .map(|(k, v)| (k.to_string(), v))
-            .collect();
-        
-        // Sort by last_used descending (most recent first)
-        users_vec.sort_by(|a, b| {
-            let a_time = a.1.last_used.unwrap_or(0);
-            let b_time = b.1.last_used.unwrap_or(0);
-            b_time.cmp(&a_time)
-        });
-        
-        // Convert to a simple object structure that Sciter understands
-        let mut result = Value::new();
-        for (uuid, user) in users_vec {
-            let mut user_obj = Value::new();
-            user_obj.set_item("username", user.username);
-            user_obj.set_item("password", user.password);
-            user_obj.set_item("strategy", user.strategy);
-            user_obj.set_item("server", user.server);
-            if let Some(nickname) = user.nickname {
-                user_obj.set_item("nickname", nickname);
+        }
+    }
+
+    fn first_run(&self) -> Reply {
+        Reply::ok(self.first_run)
+    }
+
+    /// Import the accounts of another `userdata.redb`. Replies with how many
+    /// were added.
+    fn import_accounts(&mut self, path: String) -> Reply {
+        match self.db.import_users(&path) {
+            Ok(count) => {
+                log::info!("{} conta(s) importada(s) de {}", count, path);
+                Reply::ok(count as i32)
             }
-            if let Some(last_used) = user.last_used {
-                user_obj.set_item("last_used", last_used as f64);
+            Err(e) => {
+                log::error!("Erro ao importar contas de {}: {:#}", path, e);
+                Reply::failed(&e, ErrorCode::Storage)
             }
-            result.set_item(uuid, user_obj);
         }
-        
-        result
     }
 
-    fn database_add(&mut self, user: Value) -> bool {
-        let uuid = uuid::Uuid::new_v4();
-        let mut user = user.clone();
-        user.isolate();
-        let user: UserInfo = match sciter_serde::from_value(&user) {
+    fn database_get(&self, user_id: String) -> Reply {
+        let uuid = match parse_uuid(&user_id) {
             Ok(u) => u,
+            Err(reply) => return reply,
+        };
+
+        match self.db.get_user(&uuid) {
+            Some(user) => Reply::data(ipc::account(&user)),
+            None => {
+                log::error!("User not found: {}", user_id);
+                Reply::error(ErrorCode::NotFound, DataError::AccountNotFound(uuid))
+            }
+        }
+    }
+
+    /// Every account by ID. The UI orders them by `last_used`.
+    fn database_get_all(&self) -> Reply {
+        Reply::data(
+            self.db
+                .users()
+                .iter()
+                .map(|(uuid, user)| Ok((uuid.to_string(), ipc::account(user)?)))
+                .collect(),
+        )
+    }
+
+    /// An account's password, for the edit dialog. Every call is logged and,
+    /// with a master password set, `master_password` must match it or the
+    /// reply is `denied`.
+    fn reveal_password(&self, account_id: String, master_password: String) -> Reply {
+        let uuid = match parse_uuid(&account_id) {
+            Ok(u) => u,
+            Err(reply) => return reply,
+        };
+        let Some(user) = self.db.get_user(&uuid) else {
+            log::error!("Conta {} não encontrada", account_id);
+            return Reply::error(ErrorCode::NotFound, DataError::AccountNotFound(uuid));
+        };
+        let _scope = AccountScope::enter(&user.username);
+        if !self.settings.check_master_password(&master_password) {
+            log::warn!("Senha mestra incorreta ao exibir a senha da conta {}", account_id);
+            return Reply::error(ErrorCode::Denied, "wrong master password");
+        }
+        log::warn!("Senha da conta {} exibida", account_id);
+        Reply::ok(user.password)
+    }
+
+    /// Change the master password asked by `reveal_password`, an empty
+    /// `password` removes it. `current` must match the one set now.
+    fn set_master_password(&mut self, current: String, password: String) -> Reply {
+        if !self.settings.check_master_password(&current) {
+            log::warn!("Senha mestra atual incorreta");
+            return Reply::error(ErrorCode::Denied, "wrong master password");
+        }
+        let mut saved = match Settings::load(&self.location) {
+            Ok(s) => s,
             Err(e) => {
-                eprintln!("Error deserializing user: {:?}", e);
-                return false;
+                log::error!("Erro ao carregar configurações: {:#}", e);
+                return Reply::failed(&e, ErrorCode::Storage);
             }
         };
-        match self.db.insert(&uuid, &user) {
-            Ok(_) => true,
+        if let Err(e) = saved.set_master_password(Some(password.as_str())) {
+            log::error!("Erro ao definir a senha mestra: {:#}", e);
+            return Reply::failed(&e, ErrorCode::Storage);
+        }
+        if let Err(e) = saved.save(&self.location) {
+            log::error!("Erro ao salvar configurações: {:#}", e);
+            return Reply::failed(&e, ErrorCode::Storage);
+        }
+        log::info!("Senha mestra {}", if saved.master_password.is_some() { "definida" } else { "removida" });
+        self.settings.master_password = saved.master_password;
+        Reply::done()
+    }
+
+    /// Store an account, replying with its ID. Its strategy must exist.
+    fn save_account(&mut self, uuid: uuid::Uuid, user: &UserInfo) -> Reply {
+        if let Err(e) = self.strategy.get(&user.strategy) {
+            log::error!("Error saving user: {:#}", e);
+            return Reply::failed(&e, ErrorCode::Strategy);
+        }
+        match self.db.insert(&uuid, user) {
+            Ok(_) => Reply::ok(uuid.to_string()),
             Err(e) => {
-                eprintln!("Error inserting user: {:?}", e);
-                false
+                log::error!("Error saving user: {:#}", e);
+                Reply::failed(&e, ErrorCode::Storage)
             }
         }
     }
 
-    fn database_replace(&mut self, uuid: String, user: Value) -> bool {
-        let uuid = match uuid::Uuid::parse_str(&uuid) {
+    fn database_add(&mut self, user: Value) -> Reply {
+        let user: UserInfo = match parse_value(&user, "user") {
             Ok(u) => u,
-            Err(e) => {
-                eprintln!("Error parsing UUID: {:?}", e);
-                return false;
+            Err(reply) => return reply,
+        };
+        self.save_account(uuid::Uuid::new_v4(), &user)
+    }
+
+    fn database_replace(&mut self, uuid: String, user: Value) -> Reply {
+        let uuid = match parse_uuid(&uuid) {
+            Ok(u) => u,
+            Err(reply) => return reply,
+        };
+        let mut user = user.clone();
+        user.isolate();
+        // A interface nao recebe senhas: sem `password`, manter a atual
+        if user.get_item("password").is_undefined() {
+            if let Some(current) = self.db.get_user(&uuid) {
+                user.set_item("password", current.password);
             }
+        }
+        let user: UserInfo = match parse_value(&user, "user") {
+            Ok(u) => u,
+            Err(reply) => return reply,
         };
+        self.save_account(uuid, &user)
+    }
+
+    /// The account other than `exclude_id` that already has the login of
+    /// `user`, or null. The account form asks before saving.
+    fn database_find_duplicate(&self, user: Value, exclude_id: String) -> Reply {
         let mut user = user.clone();
         user.isolate();
-        let user: UserInfo = match sciter_serde::from_value(&user) {
+        // Só o login importa, a senha pode nem ter vindo
+        if user.get_item("password").is_undefined() {
+            user.set_item("password", "");
+        }
+        let user: UserInfo = match parse_value(&user, "user") {
             Ok(u) => u,
+            Err(reply) => return reply,
+        };
+        match self.db.find_login(&user) {
+            Some((uuid, other)) if uuid.to_string() != exclude_id => Reply::data(ipc::account_entry(&uuid, &other)),
+            _ => Reply::done(),
+        }
+    }
+
+    /// Groups of accounts sharing a login, most recently used first.
+    fn duplicates_list(&self) -> Reply {
+        Reply::data(
+            self.db
+                .duplicates()
+                .iter()
+                .map(|group| {
+                    group
+                        .iter()
+                        .map(|(uuid, user)| ipc::account_entry(uuid, user))
+                        .collect::<anyhow::Result<serde_json::Value>>()
+                })
+                .collect(),
+        )
+    }
+
+    /// Merge the accounts `others` (an array of IDs) into `keep`, see
+    /// [`ddtank_rs::StoreEngine::merge`]. The merged ones go to the trash.
+    fn duplicates_merge(&mut self, keep: String, others: Value) -> Reply {
+        let keep = match parse_uuid(&keep) {
+            Ok(u) => u,
+            Err(reply) => return reply,
+        };
+        let others: Vec<String> = match parse_value(&others, "account ids") {
+            Ok(ids) => ids,
+            Err(reply) => return reply,
+        };
+        let others = match others.iter().map(|id| parse_uuid(id)).collect::<Result<Vec<_>, _>>() {
+            Ok(ids) => ids,
+            Err(reply) => return reply,
+        };
+        match self.db.merge(&keep, &others) {
+            Ok(user) => {
+                let _scope = AccountScope::enter(&user.username);
+                log::info!("{} conta(s) duplicada(s) mesclada(s) em {}", others.len(), keep);
+                Reply::done()
+            }
             Err(e) => {
-                eprintln!("Error deserializing user: {:?}", e);
-                return false;
+                log::error!("Erro ao mesclar contas: {:#}", e);
+                Reply::failed(&e, ErrorCode::Storage)
             }
+        }
+    }
+
+    fn database_delete(&mut self, uuid: String) -> Reply {
+        let uuid = match parse_uuid(&uuid) {
+            Ok(u) => u,
+            Err(reply) => return reply,
         };
-        match self.db.insert(&uuid, &user) {
-            Ok(_) => true,
+        match self.db.remove(&uuid) {
+            Ok(_) => {
+                log::info!("Conta {} movida para a lixeira", uuid);
+                Reply::done()
+            }
             Err(e) => {
-                eprintln!("Error replacing user: {:?}", e);
-                false
+                log::error!("Error deleting user: {:#}", e);
+                Reply::failed(&e, ErrorCode::Storage)
             }
         }
     }
 
-    fn database_delete(&mut self, uuid: String) -> bool {
-        let uuid = match uuid::Uuid::parse_str(&uuid) {
+    /// Accounts in the trash, most recently deleted first, without their
+    /// passwords.
+    fn trash_list(&self) -> Reply {
+        Reply::data(
+            self.db
+                .trashed()
+                .iter()
+                .map(|(uuid, deleted)| ipc::deleted_account(uuid, deleted))
+                .collect(),
+        )
+    }
+
+    fn trash_restore(&mut self, uuid: String) -> Reply {
+        let uuid = match parse_uuid(&uuid) {
             Ok(u) => u,
+            Err(reply) => return reply,
+        };
+        match self.db.restore(&uuid) {
+            Ok(_) => {
+                log::info!("Conta {} restaurada da lixeira", uuid);
+                Reply::done()
+            }
             Err(e) => {
-                eprintln!("Error parsing UUID: {:?}", e);
-                return false;
+                log::error!("Erro ao restaurar conta: {:#}", e);
+                Reply::failed(&e, ErrorCode::Storage)
             }
+        }
+    }
+
+    fn trash_purge(&mut self, uuid: String) -> Reply {
+        let uuid = match parse_uuid(&uuid) {
+            Ok(u) => u,
+            Err(reply) => return reply,
         };
-        match self.db.remove(&uuid) {
-            Ok(_) => true,
+        match self.db.purge(&uuid) {
+            Ok(_) => {
+                log::info!("Conta {} apagada definitivamente", uuid);
+                Reply::done()
+            }
             Err(e) => {
-                eprintln!("Error deleting user: {:?}", e);
-                false
+                log::error!("Erro ao apagar conta: {:#}", e);
+                Reply::failed(&e, ErrorCode::Storage)
+            }
+        }
+    }
+
+    /// Erase everything in the trash. Replies with how many accounts were
+    /// erased.
+    fn trash_empty(&mut self) -> Reply {
+        match self.db.purge_trash(std::time::Duration::ZERO) {
+            Ok(count) => {
+                log::info!("Lixeira esvaziada: {} conta(s) apagada(s)", count);
+                Reply::ok(count as i32)
+            }
+            Err(e) => {
+                log::error!("Erro ao esvaziar a lixeira: {:#}", e);
+                Reply::failed(&e, ErrorCode::Storage)
             }
         }
     }
@@ -246,74 +1022,135 @@ impl DDTankHandler {
 
 impl Drop for DDTankHandler {
     fn drop(&mut self) {
-        println!("Encerrando processos filhos...");
+        log::info!("Encerrando processos filhos...");
         if let Ok(mut processes) = self.child_processes.lock() {
-            for mut child in processes.drain(..) {
-                match child.kill() {
-                    Ok(_) => println!("Processo {} encerrado", child.id()),
-                    Err(e) => eprintln!("Erro ao encerrar processo: {}", e),
-                }
-            }
+            processes.shutdown(self.settings.shutdown_grace());
         }
     }
 }
 
 impl sciter::EventHandler for DDTankHandler {
+    fn document_complete(&mut self, root: HELEMENT, _target: HELEMENT) {
+        self.notifier.attach(root);
+    }
+
+    fn document_close(&mut self, _root: HELEMENT, _target: HELEMENT) {
+        self.notifier.detach();
+        for pending in self.logins.values() {
+            pending.cancel.cancel();
+        }
+        self.logins.clear();
+        self.games.clear();
+    }
+
+    fn on_event(
+        &mut self,
+        _root: HELEMENT,
+        _source: HELEMENT,
+        _target: HELEMENT,
+        code: BEHAVIOR_EVENTS,
+        _phase: PHASE_MASK,
+        _reason: EventReason,
+    ) -> bool {
+        if code == JOBS_DONE_EVENT {
+            self.deliver_results();
+            return true;
+        }
+        false
+    }
+
     sciter::dispatch_script_call! {
-        fn login(String, String, String, String, Value);
+        fn login_account(String, String, Value, Value, Value);
+        fn cancel_login(String);
+        fn get_logs(String, String);
         fn get_all_strategy();
-        fn play_flash(String);
-        fn open_reguinha();
+        fn settings_get();
+        fn settings_save(Value);
+        fn game_status();
+        fn focus_game(String);
+        fn kill_game(String);
+        fn open_tool(String);
+        fn tools_list();
+        fn tool_add(Value);
+        fn tool_replace(String, Value);
+        fn tool_delete(String);
+        fn first_run();
+        fn import_accounts(String);
         fn database_get(String);
         fn database_get_all();
+        fn reveal_password(String, String);
+        fn set_master_password(String, String);
         fn database_add(Value);
         fn database_replace(String, Value);
         fn database_delete(String);
+        fn database_find_duplicate(Value, String);
+        fn duplicates_list();
+        fn duplicates_merge(String, Value);
+        fn trash_list();
+        fn trash_restore(String);
+        fn trash_purge(String);
+        fn trash_empty();
     }
 }
 
+/// `src/ui` packed by build.rs.
+const UI_ARCHIVE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/ui.rc"));
+
 fn main() {
-    println!("=== DDTank-RS ===");
-    println!("Inspector habilitado! Use Ctrl+Shift+I para abrir o inspetor do Sciter.");
-    println!("Ou clique com botão direito e selecione 'Inspect Element'");
-    println!("=================\n");
-    
-    let exe_dir = std::env::current_exe()
-        .ok()
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        .unwrap_or_else(|| std::env::current_dir().unwrap());
-    
-    let ddtank_handler = DDTankHandler::new();
+    let location = Location::resolve(&Overrides::from_args(std::env::args().skip(1)));
+    if let Err(e) = location.ensure_data_dir() {
+        eprintln!("{:#}", e);
+    }
+    logging::init(LogConfig {
+        dir: Some(location.logs_dir()),
+        ..LogConfig::default()
+    });
+    log::info!("=== DDTank-RS ===");
+    log::info!(
+        "Dados em {}{}",
+        location.data_dir.display(),
+        if location.portable { " (modo portátil)" } else { "" }
+    );
+    if cfg!(debug_assertions) {
+        log::info!("Inspector habilitado! Use Ctrl+Shift+I para abrir o inspetor do Sciter.");
+        log::info!("Ou clique com botão direito e selecione 'Inspect Element'");
+    }
+
+    let settings = Settings::effective(&location);
+    let ddtank_handler = DDTankHandler::new(location, settings);
 
     let mut frame = Builder::main().create();
-    let _ = frame.set_options(Options::DebugMode(true));
-
-    // Tentar carregar recursos empacotados (`src/ui.rc` ou `ui.rc`), senão carregar arquivos do sistema de arquivos
-    let resources = std::fs::read(exe_dir.join("src").join("ui.rc"))
-        .or_else(|_| std::fs::read(exe_dir.join("ui.rc")));
-
-    match resources {
-        Ok(bytes) => {
-            if let Err(_) = frame.archive_handler(&bytes) {
-                eprintln!("Invalid archive found in ui.rc, falling back to filesystem UI");
-                let index = exe_dir.join("src").join("ui").join("index.htm");
-                let index_path = index.to_str().unwrap().replace("\\", "/");
-                frame.event_handler(ddtank_handler);
-                frame.load_file(&format!("file:///{index}", index = index_path));
-                frame.run_app();
+    // O inspetor le o DOM, incluindo senhas reveladas: so em builds de debug
+    let _ = frame.set_options(Options::DebugMode(cfg!(debug_assertions)));
+
+    frame.event_handler(ddtank_handler);
+
+    // `DDTANK_UI_DIR` aponta para uma pasta `src/ui` durante o desenvolvimento,
+    // senão usa a interface embutida no executável
+    let ui_dir = std::env::var_os("DDTANK_UI_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| std::path::PathBuf::from(dir).join("index.htm"))
+        .filter(|index| {
+            let found = index.exists();
+            if !found {
+                log::warn!("{} não encontrado, usando a interface embutida", index.display());
+            }
+            found
+        });
+    match ui_dir {
+        Some(index) => {
+            log::info!("Interface carregada de {}", index.display());
+            let index = std::env::current_dir().map(|cwd| cwd.join(&index)).unwrap_or(index);
+            let index_path = index.to_string_lossy().replace('\\', "/");
+            frame.load_file(&format!("file:///{}", index_path.trim_start_matches('/')));
+        }
+        None => {
+            if frame.archive_handler(UI_ARCHIVE).is_err() {
+                log::error!("Interface embutida inválida");
                 return;
             }
-            frame.event_handler(ddtank_handler);
             frame.load_file("this://app/index.htm");
-            frame.run_app();
-        }
-        Err(_) => {
-            // ui.rc não existe — carregar diretamente do disco (modo dev)
-            let index = exe_dir.join("src").join("ui").join("index.htm");
-            let index_path = index.to_str().unwrap().replace("\\", "/");
-            frame.event_handler(ddtank_handler);
-            frame.load_file(&format!("file:///{index}", index = index_path));
-            frame.run_app();
         }
     }
+    frame.run_app();
 }
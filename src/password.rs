@@ -0,0 +1,73 @@
+//! Password input shared by `ddtank-cli` and `ddtank-lua`: a prompt, stdin
+//! or an inherited file descriptor, so a password never has to be typed as
+//! an argument.
+
+use std::io::BufRead;
+
+use anyhow::{anyhow, Result};
+
+#[derive(clap::Args, Debug, Default)]
+pub struct PasswordArgs {
+    /// Password. A value is visible in shell history and process lists;
+    /// without one the password is prompted for without echo.
+    #[arg(short, long, num_args = 0..=1)]
+    pub password: Option<Option<String>>,
+
+    /// Read the password from the first line of stdin.
+    #[arg(long, conflicts_with = "password")]
+    pub password_stdin: bool,
+
+    /// Read the password from the first line of an already open file descriptor.
+    #[cfg(unix)]
+    #[arg(long, conflicts_with_all = ["password", "password_stdin"])]
+    pub password_fd: Option<i32>,
+}
+
+impl PasswordArgs {
+    /// The password given through one of the flags, `None` when none was used.
+    pub fn read(&self) -> Result<Option<String>> {
+        match &self.password {
+            Some(Some(password)) => return Ok(Some(password.clone())),
+            Some(None) => return prompt().map(Some),
+            None => {}
+        }
+        if self.password_stdin {
+            return read_first_line(std::io::stdin().lock()).map(Some);
+        }
+        #[cfg(unix)]
+        if let Some(fd) = self.password_fd {
+            return read_fd(fd).map(Some);
+        }
+        Ok(None)
+    }
+}
+
+/// Ask for a password on the terminal without echo.
+pub fn prompt() -> Result<String> {
+    Ok(rpassword::prompt_password("Password: ")?)
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<String> {
+    use std::os::fd::FromRawFd;
+    if fd <= 2 {
+        return Err(anyhow!(
+            "--password-fd {} is stdin, stdout or stderr, use --password-stdin instead",
+            fd
+        ));
+    }
+    // SAFETY: F_GETFD only reads the descriptor flags.
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(anyhow!("--password-fd {} is not an open file descriptor", fd));
+    }
+    // SAFETY: the descriptor is open, is not a standard stream, and the
+    // caller handed it to us to read a password from, so we own it now.
+    let file = unsafe { std::fs::File::from_raw_fd(fd) };
+    read_first_line(std::io::BufReader::new(file))
+}
+
+fn read_first_line<R: BufRead>(mut reader: R) -> Result<String> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+}
@@ -0,0 +1,208 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use regex::Regex;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Query/form parameters and JSON fields whose values are always hidden.
+const SECRET_PARAMS: &str =
+    "password|passwd|pwd|pass|token|access_token|session|sessionid|sid|key|sign|ticket|auth|code";
+
+/// Secrets shorter than this are not hidden: they would match all over
+/// unrelated text and make the logs unreadable.
+pub const MIN_SECRET_LEN: usize = 4;
+
+/// Secrets of the logins in progress, with the id of the [`SecretGuard`]
+/// that removes them.
+static REGISTERED: Mutex<Vec<(u64, Regex)>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Secrets compiled once into patterns matching them raw or URL-encoded.
+#[derive(Debug, Clone, Default)]
+pub struct Secrets(Vec<Regex>);
+
+impl Secrets {
+    /// Compile `secrets`, skipping the ones shorter than [`MIN_SECRET_LEN`].
+    pub fn new<S: AsRef<str>>(secrets: &[S]) -> Self {
+        Self(secrets.iter().filter_map(|secret| compile(secret.as_ref())).collect())
+    }
+
+    /// Replace every secret in `text`.
+    pub fn hide(&self, text: &str) -> String {
+        hide(text, &self.0)
+    }
+}
+
+/// Hides a secret from every [`redact`] call until dropped, so a password is
+/// only looked for while its login runs.
+#[must_use]
+pub struct SecretGuard(Option<u64>);
+
+impl Drop for SecretGuard {
+    fn drop(&mut self) {
+        if let (Some(id), Ok(mut registered)) = (self.0, REGISTERED.lock()) {
+            registered.retain(|(other, _)| *other != id);
+        }
+    }
+}
+
+/// Hide a secret (typically a password) from every [`redact`] call while the
+/// returned guard lives.
+pub fn register_secret(secret: &str) -> SecretGuard {
+    let id = compile(secret).and_then(|pattern| {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        REGISTERED.lock().ok()?.push((id, pattern));
+        Some(id)
+    });
+    SecretGuard(id)
+}
+
+/// Hide registered secrets and everything that looks like a credential:
+/// secret query parameters, JSON fields, cookie values and `roadclient://`
+/// session URLs.
+pub fn redact(text: &str) -> String {
+    let text = match REGISTERED.lock() {
+        Ok(registered) => hide(text, registered.iter().map(|(_, pattern)| pattern)),
+        Err(_) => text.to_owned(),
+    };
+    redact_patterns(&text)
+}
+
+/// Like [`redact`], additionally hiding `extra` secrets.
+pub fn redact_with(text: &str, extra: &Secrets) -> String {
+    redact(&extra.hide(text))
+}
+
+/// Replace every secret in `text`, raw or URL-encoded. Each character may be
+/// encoded or not and a space may be a form-encoded `+`, so partly encoded
+/// forms are caught too. Compiles `secrets` on each call, prefer [`Secrets`]
+/// for repeated use.
+pub fn redact_secrets(text: &str, secrets: &[String]) -> String {
+    Secrets::new(secrets).hide(text)
+}
+
+fn compile(secret: &str) -> Option<Regex> {
+    if secret.chars().count() < MIN_SECRET_LEN {
+        return None;
+    }
+    Some(Regex::new(&secret_pattern(secret)).expect("escaped secret is a valid regex"))
+}
+
+fn hide<'a>(text: &str, patterns: impl IntoIterator<Item = &'a Regex>) -> String {
+    let mut text = text.to_owned();
+    for pattern in patterns {
+        if pattern.is_match(&text) {
+            text = pattern.replace_all(&text, REDACTED).into_owned();
+        }
+    }
+    text
+}
+
+/// `a b` becomes `(?:a|%(?i:61))(?: |%(?i:20)|\+)(?:b|%(?i:62))`.
+fn secret_pattern(secret: &str) -> String {
+    secret
+        .chars()
+        .map(|c| {
+            let mut buf = [0; 4];
+            let encoded: String = c
+                .encode_utf8(&mut buf)
+                .bytes()
+                .map(|b| format!("%(?i:{:02x})", b))
+                .collect();
+            let plus = if c == ' ' { "|\\+" } else { "" };
+            format!("(?:{}|{}{})", regex::escape(c.encode_utf8(&mut buf)), encoded, plus)
+        })
+        .collect()
+}
+
+/// `a=1; b=2` becomes `a=[REDACTED]; b=[REDACTED]`. Attributes of a
+/// `Set-Cookie` header (`Path=/`, `HttpOnly`, ...) are left alone.
+pub fn redact_cookie_values(cookies: &str) -> String {
+    const ATTRIBUTES: [&str; 7] = [
+        "path", "domain", "expires", "max-age", "samesite", "secure", "httponly",
+    ];
+    cookies
+        .split(';')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if !ATTRIBUTES.contains(&name.trim().to_ascii_lowercase().as_str()) => {
+                format!("{}={}", name, REDACTED)
+            }
+            _ => pair.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn redact_patterns(text: &str) -> String {
+    static PATTERNS: OnceLock<[Regex; 4]> = OnceLock::new();
+    let [roadclient, param, json_field, cookie] = PATTERNS.get_or_init(|| {
+        [
+            // roadclient://host/?user=..&key=.. carries the game session
+            Regex::new(r#"(roadclient://)[^\s"'<>]+"#).unwrap(),
+            Regex::new(&format!(r"(?i)([?&;]\s*(?:{})=)[^&#\s]*", SECRET_PARAMS)).unwrap(),
+            Regex::new(&format!(r#"(?i)("(?:{})"\s*:\s*)"[^"]*""#, SECRET_PARAMS)).unwrap(),
+            Regex::new(r"(?i)((?:set-)?cookie:\s*)([^\r\n]*)").unwrap(),
+        ]
+    });
+
+    let text = roadclient.replace_all(text, format!("${{1}}{}", REDACTED));
+    let text = param.replace_all(&text, format!("${{1}}{}", REDACTED));
+    let text = json_field.replace_all(&text, format!("${{1}}\"{}\"", REDACTED));
+    let text = cookie.replace_all(&text, |caps: &regex::Captures| {
+        format!("{}{}", &caps[1], redact_cookie_values(&caps[2]))
+    });
+    text.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets() -> Vec<String> {
+        vec!["p@ss w&rd".to_owned()]
+    }
+
+    #[test]
+    fn hides_raw_and_encoded_secrets() {
+        for form in ["p@ss w&rd", "p%40ss%20w%26rd", "p%40ss+w%26rd"] {
+            let text = format!("password={}&user=joe", form);
+            assert_eq!(redact_secrets(&text, &secrets()), "password=[REDACTED]&user=joe", "{}", form);
+        }
+    }
+
+    #[test]
+    fn hides_partly_encoded_secrets() {
+        for form in ["p@ss%20w&rd", "p%40ss w%26rd", "%70@ss+w&rd"] {
+            assert_eq!(redact_secrets(form, &secrets()), REDACTED, "{}", form);
+        }
+        assert_eq!(redact_secrets("senha", &["senh".to_owned()]), REDACTED.to_owned() + "a");
+        assert_eq!(redact_secrets("senha", &["señ".to_owned()]), "senha");
+        assert_eq!(redact_secrets("se%C3%B1a", &["seña".to_owned()]), REDACTED);
+        assert_eq!(redact_secrets("se%c3%b1a", &["seña".to_owned()]), REDACTED);
+    }
+
+    #[test]
+    fn skips_short_secrets() {
+        assert_eq!(redact_secrets("a senha abc", &["abc".to_owned()]), "a senha abc");
+        assert_eq!(redact_secrets("a senha abcd", &["abcd".to_owned()]), "a senha [REDACTED]");
+    }
+
+    #[test]
+    fn registered_secrets_are_hidden_until_the_guard_drops() {
+        let guard = register_secret("registrada-123");
+        assert_eq!(redact("senha registrada-123"), "senha [REDACTED]");
+        drop(guard);
+        assert_eq!(redact("senha registrada-123"), "senha registrada-123");
+    }
+
+    #[test]
+    fn hides_session_urls_and_tokens() {
+        let text = r#"Location: roadclient://s1/?user=joe&key=abc {"token": "xyz"} /login?sign=123&id=7"#;
+        let redacted = redact(text);
+        for secret in ["abc", "xyz", "123"] {
+            assert!(!redacted.contains(secret), "{}", redacted);
+        }
+        assert!(redacted.contains("id=7"));
+    }
+}
@@ -0,0 +1,353 @@
+//! Sciter archive (`SAr`), the format of the packed UI resources in `ui.rc`
+//! that `packfolder.exe` produces and `archive_handler` reads.
+//!
+//! All integers are little endian:
+//!
+//! - `b"SAr\0"`, a `u32` node count and the nodes of a ternary search tree
+//!   over the lowercased UTF-16 file names. Each node is four `u16`: the
+//!   character, then the lo, eq and hi children, `0xFFFF` when absent. A
+//!   node with character `0` ends a name and its eq field holds the entry
+//!   number plus one.
+//! - A `u32` entry count and, per entry in name order, the `u32` offset from
+//!   the start of the archive, the stored size and the original size. An
+//!   original size of 0 means the data is stored as is, otherwise it is
+//!   LZF compressed.
+//! - The entry data.
+//!
+//! Only std is used so `build.rs` can include this file directly.
+
+use std::{
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+const MAGIC: &[u8; 4] = b"SAr\0";
+const NONE: u16 = 0xFFFF;
+
+/// Pack `(name, data)` pairs, names being `/` separated relative paths.
+pub fn pack(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut files: Vec<(Vec<u16>, &[u8])> = files
+        .iter()
+        .map(|(name, data)| (name.to_lowercase().encode_utf16().collect(), data.as_slice()))
+        .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    if let Some(pair) = files.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(invalid(format!("duplicate entry {}", String::from_utf16_lossy(&pair[0].0))));
+    }
+
+    let mut tree = Tree::default();
+    for (index, (name, _)) in files.iter().enumerate() {
+        tree.insert(name, index as u16 + 1)?;
+    }
+
+    let blobs: Vec<(Vec<u8>, u32)> = files
+        .iter()
+        .map(|(_, data)| {
+            let compressed = lzf_compress(data);
+            if compressed.is_empty() || compressed.len() >= data.len() {
+                (data.to_vec(), 0)
+            } else {
+                (compressed, data.len() as u32)
+            }
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    put_u32(&mut out, tree.nodes.len() as u32);
+    for node in &tree.nodes {
+        for field in node {
+            out.extend_from_slice(&field.to_le_bytes());
+        }
+    }
+    put_u32(&mut out, blobs.len() as u32);
+    let mut offset = out.len() + blobs.len() * 12;
+    for (blob, original) in &blobs {
+        put_u32(&mut out, offset as u32);
+        put_u32(&mut out, blob.len() as u32);
+        put_u32(&mut out, *original);
+        offset += blob.len();
+    }
+    for (blob, _) in &blobs {
+        out.extend_from_slice(blob);
+    }
+    Ok(out)
+}
+
+/// Pack every file under `dir`.
+pub fn pack_dir(dir: &Path) -> Result<Vec<u8>> {
+    let mut files = Vec::new();
+    collect_files(dir, "", &mut files)?;
+    pack(&files)
+}
+
+/// The `(name, data)` pairs of an archive, in entry order.
+pub fn unpack(archive: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    if archive.get(..4) != Some(MAGIC) {
+        return Err(invalid("not a Sciter archive"));
+    }
+    let node_count = get_u32(archive, 4)? as usize;
+    let nodes = (0..node_count)
+        .map(|i| {
+            let at = 8 + i * 8;
+            Ok([get_u16(archive, at)?, get_u16(archive, at + 2)?, get_u16(archive, at + 4)?, get_u16(archive, at + 6)?])
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let table = 8 + node_count * 8;
+    let entry_count = get_u32(archive, table)? as usize;
+    let mut names = vec![None; entry_count];
+    let mut stack = vec![(0u16, Vec::new())];
+    while let Some((index, prefix)) = stack.pop() {
+        if index == NONE || node_count == 0 {
+            continue;
+        }
+        let [ch, lo, eq, hi] = *nodes
+            .get(index as usize)
+            .ok_or_else(|| invalid("tree node out of range"))?;
+        stack.push((lo, prefix.clone()));
+        stack.push((hi, prefix.clone()));
+        if ch == 0 {
+            let slot = names
+                .get_mut((eq as usize).wrapping_sub(1))
+                .ok_or_else(|| invalid("tree entry out of range"))?;
+            *slot = Some(String::from_utf16_lossy(&prefix));
+        } else {
+            let mut prefix = prefix;
+            prefix.push(ch);
+            stack.push((eq, prefix));
+        }
+    }
+
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let name = name.ok_or_else(|| invalid(format!("entry {} has no name", i)))?;
+            let at = table + 4 + i * 12;
+            let offset = get_u32(archive, at)? as usize;
+            let size = get_u32(archive, at + 4)? as usize;
+            let original = get_u32(archive, at + 8)? as usize;
+            let blob = archive
+                .get(offset..offset + size)
+                .ok_or_else(|| invalid(format!("{} is truncated", name)))?;
+            let data = if original == 0 {
+                blob.to_vec()
+            } else {
+                lzf_decompress(blob, original)?
+            };
+            Ok((name, data))
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Tree {
+    nodes: Vec<[u16; 4]>,
+}
+
+impl Tree {
+    fn insert(&mut self, name: &[u16], value: u16) -> Result<()> {
+        let key: Vec<u16> = name.iter().copied().chain(Some(0)).collect();
+        if self.nodes.is_empty() {
+            self.push(key[0])?;
+        }
+        let (mut node, mut i) = (0, 0);
+        loop {
+            let ch = key[i];
+            let field = match ch.cmp(&self.nodes[node][0]) {
+                std::cmp::Ordering::Less => 1,
+                std::cmp::Ordering::Greater => 3,
+                std::cmp::Ordering::Equal if ch == 0 => {
+                    self.nodes[node][2] = value;
+                    return Ok(());
+                }
+                std::cmp::Ordering::Equal => {
+                    i += 1;
+                    2
+                }
+            };
+            if self.nodes[node][field] == NONE {
+                let child = self.push(key[i])?;
+                self.nodes[node][field] = child;
+            }
+            node = self.nodes[node][field] as usize;
+        }
+    }
+
+    fn push(&mut self, ch: u16) -> Result<u16> {
+        let index = self.nodes.len();
+        if index >= NONE as usize {
+            return Err(invalid("too many file names for one archive"));
+        }
+        self.nodes.push([ch, NONE, NONE, NONE]);
+        Ok(index as u16)
+    }
+}
+
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<(String, Vec<u8>)>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &format!("{}/", name), files)?;
+        } else {
+            files.push((name, std::fs::read(entry.path())?));
+        }
+    }
+    Ok(())
+}
+
+// LZF as in liblzf, with a 2^16 entry hash table and every position of a
+// match hashed.
+const HLOG: u32 = 16;
+const MAX_LIT: usize = 1 << 5;
+const MAX_OFF: usize = 1 << 13;
+const MAX_REF: usize = (1 << 8) + (1 << 3);
+
+fn lzf_compress(input: &[u8]) -> Vec<u8> {
+    let n = input.len();
+    let hash = |h: u32| (((h ^ (h << 5)) >> (24 - HLOG)).wrapping_sub(h.wrapping_mul(5)) as usize) & ((1 << HLOG) - 1);
+    let mut table = vec![0usize; 1 << HLOG];
+    let mut out = Vec::with_capacity(n + n / 16 + 1);
+    let (mut run, mut lit) = (0, 0);
+    out.push(0);
+
+    let mut ip = 0;
+    let mut hval = match input {
+        [a, b, ..] => (*a as u32) << 8 | *b as u32,
+        _ => 0,
+    };
+    while ip + 2 < n {
+        hval = (hval << 8) | input[ip + 2] as u32;
+        let slot = hash(hval);
+        let reference = table[slot];
+        table[slot] = ip;
+
+        let off = ip.wrapping_sub(reference).wrapping_sub(1);
+        if reference > 0 && off < MAX_OFF && input[reference..reference + 3] == input[ip..ip + 3] {
+            let max_len = (n - ip - 2).min(MAX_REF);
+            let mut len = 3;
+            while len < max_len && input[reference + len] == input[ip + len] {
+                len += 1;
+            }
+
+            // Close the literal run, or drop its empty control byte.
+            if lit > 0 {
+                out[run] = (lit - 1) as u8;
+            } else {
+                out.pop();
+            }
+            let encoded = len - 2;
+            if encoded < 7 {
+                out.push(((off >> 8) + (encoded << 5)) as u8);
+            } else {
+                out.push(((off >> 8) + (7 << 5)) as u8);
+                out.push((encoded - 7) as u8);
+            }
+            out.push(off as u8);
+            run = out.len();
+            lit = 0;
+            out.push(0);
+
+            let end = ip + len;
+            if end + 2 >= n {
+                ip = end;
+                break;
+            }
+            for p in ip + 1..end {
+                hval = (hval << 8) | input[p + 2] as u32;
+                table[hash(hval)] = p;
+            }
+            ip = end;
+        } else {
+            out.push(input[ip]);
+            ip += 1;
+            lit += 1;
+            if lit == MAX_LIT {
+                out[run] = (MAX_LIT - 1) as u8;
+                run = out.len();
+                lit = 0;
+                out.push(0);
+            }
+        }
+    }
+
+    while ip < n {
+        out.push(input[ip]);
+        ip += 1;
+        lit += 1;
+        if lit == MAX_LIT {
+            out[run] = (MAX_LIT - 1) as u8;
+            run = out.len();
+            lit = 0;
+            out.push(0);
+        }
+    }
+    if lit > 0 {
+        out[run] = (lit - 1) as u8;
+    } else {
+        out.pop();
+    }
+    out
+}
+
+fn lzf_decompress(input: &[u8], len: usize) -> Result<Vec<u8>> {
+    // The length comes from the header, so reserve no more than the data can yield.
+    let mut out = Vec::with_capacity(len.min(input.len().saturating_mul(8)));
+    let mut i = 0;
+    let byte = |i: usize| input.get(i).copied().ok_or_else(|| invalid("truncated LZF data"));
+    while i < input.len() {
+        let ctrl = byte(i)? as usize;
+        i += 1;
+        if ctrl < MAX_LIT {
+            let literal = input
+                .get(i..i + ctrl + 1)
+                .ok_or_else(|| invalid("truncated LZF literal"))?;
+            out.extend_from_slice(literal);
+            i += ctrl + 1;
+        } else {
+            let mut run = ctrl >> 5;
+            if run == 7 {
+                run += byte(i)? as usize;
+                i += 1;
+            }
+            let back = ((ctrl & 0x1F) << 8) + byte(i)? as usize + 1;
+            i += 1;
+            let start = out
+                .len()
+                .checked_sub(back)
+                .ok_or_else(|| invalid("LZF back reference out of range"))?;
+            for k in 0..run + 2 {
+                out.push(out[start + k]);
+            }
+        }
+        if out.len() > len {
+            return Err(invalid(format!("LZF data expands past the expected {} bytes", len)));
+        }
+    }
+    if out.len() != len {
+        return Err(invalid(format!("LZF data expands to {} bytes, expected {}", out.len(), len)));
+    }
+    Ok(out)
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn get_u16(data: &[u8], at: usize) -> Result<u16> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid("truncated archive"))
+}
+
+fn get_u32(data: &[u8], at: usize) -> Result<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("truncated archive"))
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
@@ -0,0 +1,297 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Folder name under the platform config and data directories.
+pub const APP_DIR: &str = "ddtank-rs";
+
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+/// A file with this name next to the executable turns portable mode on.
+pub const PORTABLE_MARKER: &str = "portable";
+
+/// User agent sent by strategies unless configured otherwise.
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/4.0 (compatible; MSIE 6.0; Windows NT 5.1; .NET CLR 1.0.3705;)";
+
+pub const DEFAULT_STRATEGY: &str = "337.lua";
+
+pub const DEFAULT_TRASH_DAYS: u64 = 30;
+
+/// Everything the launcher lets users configure, stored as `settings.toml`.
+/// Missing keys take their default, so old files keep loading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Extra folder of `.lua` strategies, loaded after the bundled ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scripts_dir: Option<PathBuf>,
+    /// Flash projector used by the `auto` and `flash` launchers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flash_player: Option<PathBuf>,
+    /// Launcher spec for accounts and strategies that set none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_launcher: Option<String>,
+    /// Strategy preselected for new accounts.
+    pub default_strategy: String,
+    pub user_agent: String,
+    /// Overall login timeout in seconds, see [`crate::login_timeout`].
+    pub login_timeout: u64,
+    /// Logins running at the same time.
+    pub workers: usize,
+    /// Seconds tools get to close on their own when the launcher exits.
+    pub shutdown_grace: f64,
+    /// Days deleted accounts stay in the trash, 0 keeps them until purged
+    /// by hand.
+    pub trash_days: u64,
+    /// Argon2 hash of the password asked before showing an account's
+    /// password, see [`Settings::check_master_password`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub master_password: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            scripts_dir: None,
+            flash_player: None,
+            default_launcher: None,
+            default_strategy: DEFAULT_STRATEGY.to_owned(),
+            user_agent: DEFAULT_USER_AGENT.to_owned(),
+            login_timeout: crate::DEFAULT_LOGIN_TIMEOUT.as_secs(),
+            workers: crate::jobs::DEFAULT_WORKERS,
+            shutdown_grace: crate::tools::DEFAULT_SHUTDOWN_GRACE.as_secs_f64(),
+            trash_days: DEFAULT_TRASH_DAYS,
+            master_password: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Settings saved at `location`, defaults when there is no file yet.
+    pub fn load(location: &Location) -> Result<Self> {
+        let path = location.settings_file();
+        match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).with_context(|| format!("invalid settings in {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    /// Settings in effect: the saved ones, or the defaults when the file is
+    /// broken, with the environment applied.
+    pub fn effective(location: &Location) -> Self {
+        Self::load(location)
+            .unwrap_or_else(|e| {
+                log::warn!("Configurações ignoradas: {:#}", e);
+                Self::default()
+            })
+            .with_env()
+    }
+
+    pub fn save(&self, location: &Location) -> Result<()> {
+        let path = location.settings_file();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let text = toml::to_string_pretty(self)?;
+        std::fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// These settings with the `DDTANK_*` environment variables applied on
+    /// top. Only used at runtime, never saved.
+    pub fn with_env(mut self) -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.trim().is_empty());
+        if let Some(dir) = var("DDTANK_SCRIPTS_DIR") {
+            self.scripts_dir = Some(dir.into());
+        }
+        if let Some(path) = var("DDTANK_FLASH_PLAYER") {
+            self.flash_player = Some(path.into());
+        }
+        if let Some(spec) = var("DDTANK_LAUNCHER") {
+            self.default_launcher = Some(spec);
+        }
+        if let Some(agent) = var("DDTANK_USER_AGENT") {
+            self.user_agent = agent;
+        }
+        if let Some(secs) = var("DDTANK_LOGIN_TIMEOUT").and_then(|v| v.trim().parse().ok()) {
+            self.login_timeout = secs;
+        }
+        if let Some(size) = var("DDTANK_WORKERS").and_then(|v| v.trim().parse().ok()) {
+            self.workers = size;
+        }
+        if let Some(secs) = var("DDTANK_SHUTDOWN_GRACE").and_then(|v| v.trim().parse().ok()) {
+            self.shutdown_grace = secs;
+        }
+        if let Some(days) = var("DDTANK_TRASH_DAYS").and_then(|v| v.trim().parse().ok()) {
+            self.trash_days = days;
+        }
+        self
+    }
+
+    pub fn login_timeout(&self) -> Duration {
+        Duration::from_secs(self.login_timeout)
+    }
+
+    pub fn shutdown_grace(&self) -> Duration {
+        Duration::try_from_secs_f64(self.shutdown_grace).unwrap_or(crate::tools::DEFAULT_SHUTDOWN_GRACE)
+    }
+
+    /// Require `password` to reveal account passwords, or stop requiring
+    /// one with `None`. Only the hash is kept.
+    pub fn set_master_password(&mut self, password: Option<&str>) -> Result<()> {
+        use argon2::password_hash::{PasswordHasher, SaltString};
+
+        self.master_password = match password.filter(|p| !p.is_empty()) {
+            Some(password) => {
+                let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+                    .map_err(|e| anyhow::anyhow!("failed to create salt: {}", e))?;
+                let hash = argon2::Argon2::default()
+                    .hash_password(password.as_bytes(), &salt)
+                    .map_err(|e| anyhow::anyhow!("failed to hash the master password: {}", e))?;
+                Some(hash.to_string())
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Whether `password` unlocks the account passwords, always true when
+    /// no master password is set.
+    pub fn check_master_password(&self, password: &str) -> bool {
+        use argon2::password_hash::{PasswordHash, PasswordVerifier};
+
+        let Some(hash) = &self.master_password else { return true };
+        PasswordHash::new(hash)
+            .map(|hash| argon2::Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+            .unwrap_or(false)
+    }
+
+    /// How long deleted accounts are kept, `None` when never purged
+    /// automatically.
+    pub fn trash_retention(&self) -> Option<Duration> {
+        (self.trash_days > 0).then(|| Duration::from_secs(self.trash_days * 24 * 60 * 60))
+    }
+
+    /// Glob patterns of the loose strategy scripts, later ones override
+    /// earlier scripts with the same name: `scripts/` next to the
+    /// executable, the data directory's `scripts/`, then
+    /// [`Settings::scripts_dir`].
+    pub fn script_patterns(&self, location: &Location) -> Vec<String> {
+        let mut dirs = vec![crate::exe_dir().join("scripts")];
+        if !location.portable {
+            dirs.push(location.data_dir.join("scripts"));
+        }
+        dirs.extend(self.scripts_dir.clone());
+        dirs.iter().map(|dir| dir.join("*.lua").to_string_lossy().into_owned()).collect()
+    }
+
+    /// The [`crate::BUNDLED_STRATEGIES`] overridden by the loose scripts.
+    pub fn strategy(&self, location: &Location) -> crate::Strategy {
+        let mut strategy = crate::Strategy::bundled();
+        for pattern in self.script_patterns(location) {
+            strategy.load(&pattern);
+        }
+        strategy
+    }
+}
+
+/// Command line switches shared by the binaries, they win over everything.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    /// Settings file to use instead of the default one.
+    pub config: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+    pub portable: bool,
+}
+
+impl Overrides {
+    /// Pick `--config <file>`, `--data-dir <dir>` and `--portable` out of
+    /// the arguments, ignoring anything else.
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Self {
+        let mut overrides = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => overrides.config = args.next().map(PathBuf::from),
+                "--data-dir" => overrides.data_dir = args.next().map(PathBuf::from),
+                "--portable" => overrides.portable = true,
+                _ => {}
+            }
+        }
+        overrides
+    }
+}
+
+/// Where the settings file and the user data live.
+///
+/// Portable mode keeps everything next to the executable. It is on with
+/// `--portable`, `DDTANK_PORTABLE`, a [`PORTABLE_MARKER`] file next to the
+/// executable, an existing `userdata.redb` there (installs from before
+/// settings existed) or when the platform has no config directory.
+/// Otherwise the settings go to the platform config directory
+/// (`%APPDATA%\ddtank-rs`, `~/.config/ddtank-rs`...) and the data to the
+/// platform data directory.
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub portable: bool,
+    pub config_dir: PathBuf,
+    /// Home of `userdata.redb`, `logs/` and user `scripts/`.
+    pub data_dir: PathBuf,
+    config_file: Option<PathBuf>,
+}
+
+impl Location {
+    pub fn resolve(overrides: &Overrides) -> Self {
+        let exe_dir = crate::exe_dir();
+        let env_path = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+        let platform = dirs::config_dir().zip(dirs::data_dir());
+        let portable = overrides.portable
+            || env_path("DDTANK_PORTABLE").is_some()
+            || exe_dir.join(PORTABLE_MARKER).exists()
+            || exe_dir.join(crate::DATABASE_FILE).exists()
+            || platform.is_none();
+
+        let (config_dir, data_dir) = match platform {
+            Some((config, data)) if !portable => (config.join(APP_DIR), data.join(APP_DIR)),
+            _ => (exe_dir.clone(), exe_dir),
+        };
+        let data_dir = overrides
+            .data_dir
+            .clone()
+            .or_else(|| env_path("DDTANK_DATA_DIR"))
+            .unwrap_or(data_dir);
+
+        Self {
+            portable,
+            config_dir,
+            data_dir,
+            config_file: overrides.config.clone().or_else(|| env_path("DDTANK_CONFIG")),
+        }
+    }
+
+    pub fn settings_file(&self) -> PathBuf {
+        self.config_file
+            .clone()
+            .unwrap_or_else(|| self.config_dir.join(SETTINGS_FILE))
+    }
+
+    pub fn database(&self) -> PathBuf {
+        self.data_dir.join(crate::DATABASE_FILE)
+    }
+
+    pub fn logs_dir(&self) -> PathBuf {
+        self.data_dir.join("logs")
+    }
+
+    /// Create the data directory if needed.
+    pub fn ensure_data_dir(&self) -> Result<&Path> {
+        std::fs::create_dir_all(&self.data_dir)
+            .with_context(|| format!("failed to create {}", self.data_dir.display()))?;
+        Ok(&self.data_dir)
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    path::PathBuf,
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// An external program the launcher can start: a ruler, a calculator, a
+/// recorder, a chat tool...
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInfo {
+    pub name: String,
+    /// Executable, relative paths are taken from the executable's directory.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Working directory, default to the tool's own directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Emoji or image path shown on the toolbar button.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Start the tool together with every game.
+    #[serde(default)]
+    pub start_with_game: bool,
+    /// Leave the tool running when the launcher exits.
+    #[serde(default)]
+    pub keep_alive: bool,
+}
+
+impl ToolInfo {
+    /// The ruler shipped in `tools/`, or next to the executable in older releases.
+    pub fn reguinha() -> Self {
+        let exe_dir = crate::exe_dir();
+        let path = if !exe_dir.join("tools/reguinha.exe").exists() && exe_dir.join("reguinha.exe").exists() {
+            "reguinha.exe"
+        } else {
+            "tools/reguinha.exe"
+        };
+        Self {
+            name: "Régua".to_owned(),
+            path: path.to_owned(),
+            args: Vec::new(),
+            cwd: None,
+            icon: Some("📏".to_owned()),
+            start_with_game: false,
+            keep_alive: false,
+        }
+    }
+
+    /// Absolute path of the executable.
+    pub fn resolved_path(&self) -> PathBuf {
+        crate::exe_dir().join(&self.path)
+    }
+
+    pub fn spawn(&self) -> Result<Child> {
+        let path = self.resolved_path();
+        let cwd = match &self.cwd {
+            Some(cwd) => crate::exe_dir().join(cwd),
+            None => path
+                .parent()
+                .map(PathBuf::from)
+                .unwrap_or_else(crate::exe_dir),
+        };
+        Command::new(&path)
+            .args(&self.args)
+            .current_dir(cwd)
+            .spawn()
+            .with_context(|| format!("failed to start {} ({})", self.name, path.display()))
+    }
+}
+
+/// How long tools get to close on their own at shutdown before being killed,
+/// see [`crate::Settings::shutdown_grace`].
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+const SHUTDOWN_POLL: Duration = Duration::from_millis(100);
+
+struct RunningTool {
+    tool_id: String,
+    name: String,
+    keep_alive: bool,
+    child: Child,
+}
+
+/// Tools started by the launcher. Exited ones are reaped whenever the list
+/// is touched, so they neither pile up nor linger as zombies.
+#[derive(Default)]
+pub struct ToolProcesses {
+    running: Vec<RunningTool>,
+}
+
+impl ToolProcesses {
+    pub fn push(&mut self, tool_id: &str, tool: &ToolInfo, child: Child) {
+        self.reap();
+        self.running.push(RunningTool {
+            tool_id: tool_id.to_owned(),
+            name: tool.name.clone(),
+            keep_alive: tool.keep_alive,
+            child,
+        });
+    }
+
+    /// Forget the tools that exited.
+    pub fn reap(&mut self) {
+        self.running.retain_mut(|tool| match tool.child.try_wait() {
+            Ok(None) => true,
+            Ok(Some(status)) => {
+                log::info!("{} encerrado ({})", tool.name, status);
+                false
+            }
+            Err(e) => {
+                log::warn!("Erro ao verificar {}: {}", tool.name, e);
+                false
+            }
+        });
+    }
+
+    pub fn is_running(&mut self, tool_id: &str) -> bool {
+        self.reap();
+        self.running.iter().any(|tool| tool.tool_id == tool_id)
+    }
+
+    /// Ask every tool to close, give them `grace` to do so, then kill and
+    /// reap the ones still running. `keep_alive` tools are left alone.
+    pub fn shutdown(&mut self, grace: Duration) {
+        let (kept, mut closing): (Vec<_>, Vec<_>) =
+            self.running.drain(..).partition(|tool| tool.keep_alive);
+        for tool in kept {
+            log::info!("{} continua aberto (PID {})", tool.name, tool.child.id());
+        }
+
+        for tool in &mut closing {
+            if let Err(e) = request_exit(&tool.child) {
+                log::debug!("{} não aceitou o pedido de encerramento: {:#}", tool.name, e);
+            }
+        }
+
+        let deadline = Instant::now() + grace;
+        loop {
+            closing.retain_mut(|tool| match tool.child.try_wait() {
+                Ok(None) => true,
+                Ok(Some(_)) => {
+                    log::info!("Processo {} ({}) encerrado", tool.child.id(), tool.name);
+                    false
+                }
+                Err(_) => true,
+            });
+            if closing.is_empty() || Instant::now() >= deadline {
+                break;
+            }
+            std::thread::sleep(SHUTDOWN_POLL);
+        }
+
+        for mut tool in closing {
+            log::warn!("{} não fechou em {:?}, forçando", tool.name, grace);
+            if let Err(e) = tool.child.kill() {
+                log::error!("Erro ao encerrar processo {}: {}", tool.child.id(), e);
+            }
+            match tool.child.wait() {
+                Ok(_) => log::info!("Processo {} ({}) encerrado", tool.child.id(), tool.name),
+                Err(e) => log::error!("Erro ao aguardar processo {}: {}", tool.child.id(), e),
+            }
+        }
+    }
+}
+
+/// Politely ask a process to exit: `SIGTERM` on Unix, a close request
+/// (`taskkill` without `/F`) to its windows on Windows.
+fn request_exit(child: &Child) -> Result<()> {
+    let pid = child.id().to_string();
+    let status = if cfg!(target_os = "windows") {
+        Command::new("taskkill").args(["/PID", &pid, "/T"]).status()
+    } else {
+        Command::new("kill").args(["-TERM", &pid]).status()
+    }
+    .context("failed to run the terminate helper")?;
+    if !status.success() {
+        return Err(anyhow!("terminate helper exited with {}", status));
+    }
+    Ok(())
+}
@@ -1,15 +1,11 @@
-const add_account = (username, password, strategy, server, nickname = undefined) => {
-  const account = {
-    username: username,
-    password: password,
-    strategy: strategy,
-    server: server,
-    nickname: nickname
-  }
-  return Window.this.xcall("database_add", account);
-}
+import { call, replyData, pickFields } from "./utils.js";
+import { SHAPES } from "./shapes.js";
+
+// Devolvem a resposta do backend: { ok: true, data } ou { ok: false, code, message }
+// Campos novos de UserInfo chegam aqui por SHAPES.AccountForm
+const add_account = (data) => call("database_add", pickFields(data, SHAPES.AccountForm));
 
-const get_account = id => Window.this.xcall("database_get", id);
+const get_account = id => call("database_get", id);
 const get_all_accounts = () => {
   // let account_list = []
   // for (let account of root.accountsByDate) {
@@ -17,8 +13,8 @@ const get_all_accounts = () => {
   //   account_list.push({ id, username, password, strategy, server, nickname });
   // }
   // return account_list;
-  let account = Window.this.xcall("database_get_all");
-  return account
+  // Contas por id, vazio se o banco nao puder ser lido
+  return replyData(call("database_get_all"), {});
 }
 
 const delete_account = id => {
@@ -26,18 +22,33 @@ const delete_account = id => {
   // let result = root.accountsByDate.delete(account.date, account);
   // result = root.accountsById.delete(account.id);
   // storage.commit();
-  return Window.this.xcall("database_delete", id);
+  return call("database_delete", id);
 }
 
 const replace_account = (id, obj) => {
-  return Window.this.xcall("database_replace", id, obj);
+  return call("database_replace", id, pickFields(obj, SHAPES.AccountForm));
 }
 
+// Trazer de volta uma conta da lixeira
+const restore_account = id => call("trash_restore", id);
+
+// Outra conta com o mesmo usuario e estrategia, em qualquer servidor, ou null
+const find_duplicate = (data, exclude_id) =>
+  replyData(call("database_find_duplicate", pickFields(data, SHAPES.AccountForm), exclude_id || ""), null);
+
+// Grupos de contas repetidas e a mescla de um grupo em uma das contas
+const get_duplicates = () => replyData(call("duplicates_list"), []);
+const merge_accounts = (keep_id, other_ids) => call("duplicates_merge", keep_id, other_ids);
+
 
 export {
   get_account,
   get_all_accounts,
   add_account,
   replace_account,
-  delete_account
+  delete_account,
+  restore_account,
+  find_duplicate,
+  get_duplicates,
+  merge_accounts
 }
@@ -1,10 +1,13 @@
 import * as account_db from "./accountdb.js";
-import { SERVER_NAMES, SERVERS, GAME_STRATEGY } from "./constants.js";
+import { SERVER_NAMES, SERVERS, GAME_STRATEGY, LOGIN_OUTCOMES } from "./constants.js";
 import { 
     getAccountDisplayName, 
     filterAccounts, 
     sortByLastUsed, 
-    isValidGameUrl 
+    call,
+    replyData,
+    replyError,
+    replyDetail
 } from "./utils.js";
 
 const { signal } = Reactor;
@@ -13,15 +16,25 @@ const { signal } = Reactor;
 const state = {
     accounts: signal(account_db.get_all_accounts()),
     loadingAccounts: signal({}),
-    searchText: signal("")
+    loginAttempts: signal({}),
+    pickedServers: signal({}),
+    games: signal(replyData(call("game_status"), {})),
+    tools: signal(replyData(call("tools_list"), [])),
+    searchText: signal(""),
+    toast: signal(null)
 };
 
-// Remover conta de teste apos inicializar
-const TEST_ACCOUNT_UUID = "00000000-0000-0000-0000-000000000001";
-if (state.accounts.value[TEST_ACCOUNT_UUID]) {
-    account_db.delete_account(TEST_ACCOUNT_UUID);
-    state.accounts.value = Object.assign({}, account_db.get_all_accounts());
-}
+const TOAST_DURATION = 8000;
+
+// Configuracoes salvas, vazio se nao puderem ser lidas
+const savedSettings = () => replyData(call("settings_get"), {});
+
+// Erro de uma chamada ao backend, com o texto do seu codigo
+const showError = (title, reply) =>
+    Window.this.modal(<error>
+        ❌ {title}<br/><br/>{replyError(reply)}
+        {replyDetail(reply) && <div style="margin-top: 8px; font-size: 0.85em; opacity: 0.7;">{replyDetail(reply)}</div>}
+    </error>);
 
 // Computed Values
 const filteredAccounts = () => {
@@ -30,6 +43,18 @@ const filteredAccounts = () => {
     return Object.fromEntries(sorted);
 };
 
+// Servidor escolhido no card, ou vazio para o servidor padrao da conta
+const pickedServer = (accountId) => {
+    const account = state.accounts.value[accountId];
+    const server = state.pickedServers.value[accountId];
+    return account && (account.servers || []).some(entry => entry.server === server) ? server : "";
+};
+
+const serverDisplay = (id) => {
+    const server = SERVERS.find(s => s.id === id);
+    return server ? `${server.name} (${server.range})` : id;
+};
+
 // UI Components
 const Header = () => (
     <header>
@@ -41,6 +66,7 @@ const Header = () => (
         </p>
         <SearchBar />
         <ActionButtons />
+        <Toolbar />
     </header>
 );
 
@@ -60,19 +86,41 @@ const ActionButtons = () => (
     <div style="margin-top: 15px;">
         <button onclick={AccountActions.showAddDialog}>➕ Adicionar Conta</button>
         <button onclick={AccountActions.refresh}>🔄 Atualizar Lista</button>
-        <button onclick={() => Window.this.xcall('open_reguinha')}>📏 Abrir Régua</button>
+        <button onclick={AccountActions.showLogs}>📜 Logs</button>
+        <button onclick={ToolActions.showManager}>🧰 Ferramentas</button>
+        <button onclick={TrashActions.show}>🗑️ Lixeira</button>
+        <button onclick={DuplicateActions.show}>👥 Duplicadas</button>
+        <button onclick={SettingsActions.show}>⚙️ Configurações</button>
+    </div>
+);
+
+const Toolbar = () => (
+    <div class="toolbar">
+        {state.tools.value.map(tool =>
+            <button
+                class={tool.running ? "tool-btn running" : "tool-btn"}
+                title={tool.path}
+                onclick={() => ToolActions.open(tool)}
+            >
+                {tool.icon || "🔧"} {tool.name}
+            </button>
+        )}
     </div>
 );
 
 const AccountCard = ({ accountId, account }) => {
     const isLoading = state.loadingAccounts.value[accountId];
-    const server = SERVERS.find(s => s.id === account.server);
-    const serverDisplay = server ? `${server.name} (${server.range})` : account.server;
-    const displayName = getAccountDisplayName(account);
+    const retry = state.loginAttempts.value[accountId];
+    const game = state.games.value[accountId];
+    const servers = account.servers || [];
+    const server = pickedServer(accountId) || account.server;
+    // Personagem do servidor escolhido, se tiver nome proprio
+    const entry = servers.find(e => e.server === server);
+    const displayName = (entry && entry.nickname) || getAccountDisplayName(account);
     
     return (
         <div 
-            class={isLoading ? "account loading" : "account"}
+            class={isLoading ? "account loading" : game ? "account in-game" : "account"}
             onclick={() => !isLoading && AccountActions.login(accountId)}
         >
             <div class="account-content">
@@ -81,22 +129,85 @@ const AccountCard = ({ accountId, account }) => {
                 </div>
                 <div class="account-detail">{displayName}</div>
             </div>
-            <div class="server-badge">🌐 {serverDisplay}</div>
-            <div class="account-actions">
-                <button class="action-btn" onclick={() => { AccountActions.showEditDialog(accountId); return false; }}>✏️</button>
-                <button class="action-btn delete" onclick={() => { AccountActions.delete(accountId); return false; }}>🗑️</button>
-            </div>
+            {isLoading && retry && (
+                <div class="retry-badge" title={retry.message}>
+                    🔁 Tentativa {retry.attempt + 1}/{retry.maxAttempts}
+                </div>
+            )}
+            {servers.length > 1 ? (
+                <div class="server-badge" onclick={() => false}>
+                    🌐 <select|dropdown class="server-picker" onchange={(e) => AccountActions.pickServer(accountId, e.target.value)}>
+                        {servers.map(choice =>
+                            <option value={choice.server} selected={choice.server === server}>
+                                {choice.nickname ? `${choice.nickname} · ` : ""}{serverDisplay(choice.server)}
+                            </option>
+                        )}
+                    </select>
+                </div>
+            ) : (
+                <div class="server-badge">🌐 {serverDisplay(account.server)}</div>
+            )}
+            {game && <div class="game-badge" title={`PID ${game.pid}`}>🎮 Em jogo</div>}
+            {isLoading ? (
+                <div class="account-actions">
+                    <button class="action-btn cancel" title="Cancelar login" onclick={() => { AccountActions.cancel(accountId); return false; }}>✖️</button>
+                </div>
+            ) : (
+                <div class="account-actions">
+                    {game && <button class="action-btn" title="Mostrar o jogo" onclick={() => { AccountActions.focusGame(accountId); return false; }}>🎯</button>}
+                    {game && <button class="action-btn delete" title="Fechar o jogo" onclick={() => { AccountActions.killGame(accountId); return false; }}>⏹️</button>}
+                    <button class="action-btn" onclick={() => { AccountActions.showEditDialog(accountId); return false; }}>✏️</button>
+                    <button class="action-btn delete" onclick={() => { AccountActions.delete(accountId); return false; }}>🗑️</button>
+                </div>
+            )}
         </div>
     );
 };
 
+const EmptyState = () => (
+    <div class="empty-state">
+        <p>Nenhuma conta cadastrada ainda.</p>
+        <button onclick={AccountActions.showAddDialog}>➕ Adicionar Conta</button>
+        <button onclick={AccountActions.import}>📥 Importar Contas</button>
+    </div>
+);
+
+// Aviso temporario com uma acao, ex.: desfazer a exclusao de uma conta
+const Toast = () => {
+    const toast = state.toast.value;
+    if (!toast) return <div></div>;
+    return (
+        <div class="toast">
+            <span>{toast.message}</span>
+            <button onclick={() => { Toasts.hide(); toast.action(); }}>{toast.actionLabel}</button>
+        </div>
+    );
+};
+
+const Toasts = {
+    timer: null,
+
+    show: (message, actionLabel, action) => {
+        clearTimeout(Toasts.timer);
+        state.toast.value = { message, actionLabel, action };
+        Toasts.timer = setTimeout(Toasts.hide, TOAST_DURATION);
+    },
+
+    hide: () => {
+        clearTimeout(Toasts.timer);
+        state.toast.value = null;
+    }
+};
+
 const AccountList = () => (
     <main>
-        <div id="account-list">
-            {Object.entries(filteredAccounts()).map(([accountId, account]) => 
-                <AccountCard accountId={accountId} account={account} />
-            )}
-        </div>
+        {Object.keys(state.accounts.value).length === 0 ? <EmptyState /> :
+            <div id="account-list">
+                {Object.entries(filteredAccounts()).map(([accountId, account]) => 
+                    <AccountCard accountId={accountId} account={account} />
+                )}
+            </div>
+        }
     </main>
 );
 
@@ -104,97 +215,377 @@ const AccountList = () => (
 const AccountActions = {
     login: (accountId) => {
         if (state.loadingAccounts.value[accountId]) return;
-
-        const account = account_db.get_account(accountId);
-        const { strategy, username, password, server } = account;
-
-        // Update last used timestamp
-        account.last_used = Date.now();
-        account_db.replace_account(accountId, account);
-        // Forcar atualizacao do estado criando nova referencia
-        state.accounts.value = Object.assign({}, account_db.get_all_accounts());
+        if (state.games.value[accountId]) {
+            // Conta ja aberta: trazer a janela do jogo em vez de logar de novo
+            AccountActions.focusGame(accountId);
+            return;
+        }
 
         // Set loading state
         state.loadingAccounts.value = { ...state.loadingAccounts.value, [accountId]: true };
 
-        Window.this.xcall("login", strategy, username, password, server, (response) => {
+        const finish = () => {
             // Clear loading state
             const newLoading = { ...state.loadingAccounts.value };
             delete newLoading[accountId];
             state.loadingAccounts.value = newLoading;
 
-            // Atualizar lista de contas para refletir nova ordenação por last_used
+            const newAttempts = { ...state.loginAttempts.value };
+            delete newAttempts[accountId];
+            state.loginAttempts.value = newAttempts;
+
+            // O backend atualiza last_used ao fim do login: reordenar a lista
             state.accounts.value = Object.assign({}, account_db.get_all_accounts());
+        };
 
-            if (isValidGameUrl(response)) {
-                Window.this.xcall("play_flash", response);
-            } else if (!response.startsWith("Abrindo jogo")) {
-                Window.this.modal(<error>❌ Erro ao conectar:<br/><br/>{response}</error>);
+        // Com URL de jogo o backend ja abriu o jogo: `launch` e a resposta disso
+        const onDone = (response, outcome, launch) => {
+            finish();
+
+            switch (outcome) {
+                case LOGIN_OUTCOMES.OK:
+                    if (launch) {
+                        AccountActions.gameLaunched(launch);
+                    } else if (!response.startsWith("Abrindo jogo")) {
+                        Window.this.modal(<error>❌ Erro ao conectar:<br/><br/>{response}</error>);
+                    }
+                    break;
+                case LOGIN_OUTCOMES.CANCELLED:
+                    break;
+                case LOGIN_OUTCOMES.TIMEOUT:
+                    Window.this.modal(<error>⏱️ O servidor demorou demais para responder.<br/><br/>Tente novamente em instantes.</error>);
+                    break;
+                default:
+                    Window.this.modal(<error>❌ Erro ao conectar:<br/><br/>{response}</error>);
             }
-        });
+        };
+
+        // Falha temporaria (rede, 5xx): o backend tenta de novo apos `delay` segundos
+        const onRetry = (attempt, maxAttempts, delay, message) => {
+            state.loginAttempts.value = {
+                ...state.loginAttempts.value,
+                [accountId]: { attempt, maxAttempts, delay, message }
+            };
+        };
+
+        const onExit = (code) => {
+            ToolActions.refresh();
+            AccountActions.refreshGames();
+            // null: processo encerrado a forca; 0: saida normal
+            if (code !== null && code !== 0) {
+                Window.this.modal(<error>⚠️ O jogo encerrou com o código {code}.<br/><br/>Verifique o launcher configurado para a conta.</error>);
+            }
+        };
+
+        const reply = call("login_account", accountId, pickedServer(accountId), onDone, onRetry, onExit);
+        if (reply.code === "duplicate") {
+            finish();
+            AccountActions.refreshGames();
+            Window.this.modal(<info>🎮 Esta conta já está em jogo.</info>);
+            return;
+        }
+        // Conta, estratégia ou servidor inexistente: erro na resposta, sem callback
+        if (!reply.ok) {
+            finish();
+            showError("Erro ao conectar", reply);
+            return;
+        }
+
+        // O callback pode ter rodado antes do retorno (ex.: fila de logins indisponível)
+        if (state.loadingAccounts.value[accountId]) {
+            state.loadingAccounts.value = { ...state.loadingAccounts.value, [accountId]: reply.data };
+        }
+    },
+
+    gameLaunched: (reply) => {
+        if (reply.code === "duplicate") {
+            Window.this.modal(<info>🎮 Esta conta já está em jogo.</info>);
+        } else if (!reply.ok) {
+            showError("Não foi possível abrir o jogo", reply);
+        }
+        AccountActions.refreshGames();
+        // Ferramentas com "abrir junto com o jogo" podem ter sido iniciadas
+        ToolActions.refresh();
+    },
+
+    refreshGames: () => {
+        state.games.value = replyData(call("game_status"), {});
+    },
+
+    focusGame: (accountId) => {
+        if (!call("focus_game", accountId).ok) {
+            Window.this.modal(<info>🎮 Esta conta já está em jogo, mas a janela não pôde ser trazida para frente.</info>);
+        }
+    },
+
+    killGame: (accountId) => {
+        const account = state.accounts.value[accountId];
+        const name = account ? getAccountDisplayName(account) : accountId;
+        if (Window.this.modal(<question>Fechar o jogo de {name}?</question>) !== "yes") return;
+        const reply = call("kill_game", accountId);
+        if (!reply.ok) {
+            showError("Não foi possível fechar o jogo", reply);
+        }
+    },
+
+    // Servidor do proximo login da conta, ate fechar o launcher
+    pickServer: (accountId, server) => {
+        state.pickedServers.value = { ...state.pickedServers.value, [accountId]: server };
+    },
+
+    cancel: (accountId) => {
+        const handle = state.loadingAccounts.value[accountId];
+        if (typeof handle === "string") {
+            call("cancel_login", handle);
+        }
     },
 
     showAddDialog: () => {
         const data = Window.this.modal({
             url: __DIR__ + "../htm/add-account.htm",
-            parameters: { strategy_list: Window.this.xcall("get_all_strategy") }
+            parameters: {
+                strategy_list: replyData(call("get_all_strategy"), []),
+                default_strategy: savedSettings().default_strategy,
+                find_duplicate: (data) => account_db.find_duplicate(data)
+            }
         });
 
         if (data) {
-            const { username, password, strategy, server, nickname } = data;
-            const success = account_db.add_account(username, password, strategy, server, nickname);
-            if (success) {
+            const reply = account_db.add_account(data);
+            if (reply.ok) {
                 // Forcar atualizacao do estado criando nova referencia
                 state.accounts.value = Object.assign({}, account_db.get_all_accounts());
             } else {
-                Window.this.modal(<error>❌ Erro ao adicionar conta</error>);
+                showError("Erro ao adicionar conta", reply);
             }
         }
     },
 
     showEditDialog: (accountId) => {
-        const account = account_db.get_account(accountId);
+        const current = account_db.get_account(accountId);
+        if (!current.ok) {
+            showError("Erro ao abrir conta", current);
+            return;
+        }
+        const account = current.data;
         const data = Window.this.modal({
             url: __DIR__ + "../htm/edit-account.htm",
             parameters: {
-                strategy_list: Window.this.xcall("get_all_strategy"),
-                account
+                strategy_list: replyData(call("get_all_strategy"), []),
+                account,
+                reveal: () => AccountActions.revealPassword(accountId),
+                find_duplicate: (data) => account_db.find_duplicate(data, accountId)
             }
         });
 
         if (data) {
-            const { username, password, strategy, server, nickname } = data;
-            Object.assign(account, { username, password, strategy, server, nickname });
-            const success = account_db.replace_account(accountId, account);
-            if (success) {
+            // Sem senha nova o backend mantem a atual
+            const { password, ...changes } = data;
+            Object.assign(account, changes);
+            if (password) account.password = password;
+            const reply = account_db.replace_account(accountId, account);
+            if (reply.ok) {
                 // Forcar atualizacao do estado criando nova referencia
                 state.accounts.value = Object.assign({}, account_db.get_all_accounts());
             } else {
-                Window.this.modal(<error>❌ Erro ao atualizar conta</error>);
+                showError("Erro ao atualizar conta", reply);
             }
         }
     },
 
+    // Senha salva de uma conta, ou null se a senha mestra nao conferir
+    revealPassword: (accountId) => {
+        let master = "";
+        if (savedSettings().master_password) {
+            const data = Window.this.modal({
+                url: __DIR__ + "../htm/master-password.htm",
+                parameters: { mode: "unlock" }
+            });
+            if (!data) return null;
+            master = data.password;
+        }
+        const reply = call("reveal_password", accountId, master);
+        if (!reply.ok) {
+            showError("Não foi possível exibir a senha", reply);
+            return null;
+        }
+        return reply.data;
+    },
+
     delete: (accountId) => {
-        const success = account_db.delete_account(accountId);
-        if (success) {
+        const name = getAccountDisplayName(state.accounts.value[accountId]);
+        if (Window.this.modal(<question>Mover {name} para a lixeira?</question>) !== "yes") return;
+        const reply = account_db.delete_account(accountId);
+        if (reply.ok) {
             // Forcar atualizacao do estado criando nova referencia
             state.accounts.value = Object.assign({}, account_db.get_all_accounts());
+            Toasts.show(`🗑️ ${name} foi para a lixeira`, "Desfazer", () => AccountActions.undoDelete(accountId));
         } else {
-            Window.this.modal(<error>❌ Erro ao deletar conta</error>);
+            showError("Erro ao deletar conta", reply);
+        }
+    },
+
+    undoDelete: (accountId) => {
+        const reply = account_db.restore_account(accountId);
+        if (!reply.ok) {
+            showError("Erro ao restaurar conta", reply);
+        }
+        state.accounts.value = Object.assign({}, account_db.get_all_accounts());
+    },
+
+    import: () => {
+        const url = Window.this.selectFile({
+            mode: "open",
+            filter: "Banco de contas (*.redb)|*.redb|Todos os arquivos (*.*)|*.*",
+            caption: "Importar contas"
+        });
+        if (!url) return;
+
+        const reply = call("import_accounts", URL.toPath(url));
+        if (!reply.ok) {
+            showError("Não foi possível importar as contas", reply);
+            return;
         }
+        const count = reply.data;
+        // Forcar atualizacao do estado criando nova referencia
+        state.accounts.value = Object.assign({}, account_db.get_all_accounts());
+        Window.this.modal(<info>📥 {count} conta(s) importada(s).</info>);
     },
 
+    showLogs: () => {
+        const accounts = Object.values(state.accounts.value).map(account => ({
+            username: account.username,
+            name: getAccountDisplayName(account)
+        }));
+        new Window({
+            url: __DIR__ + "../htm/log-viewer.htm",
+            parameters: {
+                fetch_logs: (level, account) => replyData(call("get_logs", level, account), []),
+                accounts
+            }
+        });
+    },
+
+    refresh: () => {
+        // Forcar atualizacao do estado criando nova referencia
+        state.accounts.value = Object.assign({}, account_db.get_all_accounts());
+    }
+};
+
+const ToolActions = {
     refresh: () => {
+        state.tools.value = replyData(call("tools_list"), []);
+    },
+
+    open: (tool) => {
+        const reply = call("open_tool", tool.id);
+        if (!reply.ok) {
+            showError(`Não foi possível abrir ${tool.name}`, reply);
+        }
+        ToolActions.refresh();
+    },
+
+    showManager: () => {
+        Window.this.modal({
+            url: __DIR__ + "../htm/tools.htm",
+            parameters: {
+                list: () => replyData(call("tools_list"), []),
+                add: (tool) => call("tool_add", tool),
+                replace: (id, tool) => call("tool_replace", id, tool),
+                remove: (id) => call("tool_delete", id)
+            }
+        });
+        ToolActions.refresh();
+    }
+};
+
+const Onboarding = {
+    start: () => {
+        if (!replyData(call("first_run"), false)) return;
+        const { data_dir, portable } = savedSettings();
+        const choice = Window.this.modal({
+            url: __DIR__ + "../htm/welcome.htm",
+            parameters: { data_dir, portable }
+        });
+        if (choice === "add") AccountActions.showAddDialog();
+        else if (choice === "import") AccountActions.import();
+    }
+};
+
+document.on("ready", Onboarding.start);
+
+const TrashActions = {
+    show: () => {
+        Window.this.modal({
+            url: __DIR__ + "../htm/trash.htm",
+            parameters: {
+                trash_days: savedSettings().trash_days,
+                list: () => replyData(call("trash_list"), []),
+                restore: (id) => account_db.restore_account(id),
+                purge: (id) => call("trash_purge", id),
+                empty: () => call("trash_empty")
+            }
+        });
+        Toasts.hide();
         // Forcar atualizacao do estado criando nova referencia
         state.accounts.value = Object.assign({}, account_db.get_all_accounts());
     }
 };
 
+const DuplicateActions = {
+    show: () => {
+        Window.this.modal({
+            url: __DIR__ + "../htm/duplicates.htm",
+            parameters: {
+                list: account_db.get_duplicates,
+                merge: account_db.merge_accounts
+            }
+        });
+        // Forcar atualizacao do estado criando nova referencia
+        state.accounts.value = Object.assign({}, account_db.get_all_accounts());
+    }
+};
+
+const SettingsActions = {
+    show: () => {
+        const data = Window.this.modal({
+            url: __DIR__ + "../htm/settings.htm",
+            parameters: {
+                settings: savedSettings(),
+                strategy_list: replyData(call("get_all_strategy"), []),
+                change_master_password: SettingsActions.changeMasterPassword
+            }
+        });
+        if (!data) return;
+        const reply = call("settings_save", data);
+        if (!reply.ok) {
+            showError("Erro ao salvar configurações", reply);
+        }
+    },
+
+    changeMasterPassword: () => {
+        const data = Window.this.modal({
+            url: __DIR__ + "../htm/master-password.htm",
+            parameters: {
+                mode: "change",
+                has_current: savedSettings().master_password
+            }
+        });
+        if (!data) return;
+        const reply = call("set_master_password", data.current, data.password);
+        if (reply.ok) {
+            Window.this.modal(<info>🔒 Senha mestra {data.password ? "definida" : "removida"}.</info>);
+        } else {
+            showError("Não foi possível alterar a senha mestra", reply);
+        }
+    }
+};
+
 // Main App Component
 export const App = () => (
     <div>
         <Header />
         <AccountList />
+        <Toast />
     </div>
 );
@@ -1,4 +1,4 @@
-// Game Configuration
+// Game Configuration (padrao quando a configuracao default_strategy nao existe)
 export const GAME_STRATEGY = "337.lua";
 
 // Server Configuration
@@ -17,6 +17,29 @@ export const SERVER_NAMES = SERVERS.reduce((acc, server) => {
     return acc;
 }, {});
 
+// Login Outcomes (segundo argumento do callback de login)
+export const LOGIN_OUTCOMES = {
+    OK: "ok",
+    ERROR: "error",
+    CANCELLED: "cancelled",
+    TIMEOUT: "timeout"
+};
+
+// Error Codes (respostas { ok: false, code, message } do backend)
+export const ERROR_MESSAGES = {
+    validation: "Dados inválidos. Confira os campos e tente novamente.",
+    duplicate: "Já existe um cadastro igual.",
+    not_found: "Não encontrado. Pode ter sido removido em outra janela.",
+    storage: "Não foi possível ler ou gravar os dados. Feche outros launchers que estejam usando o mesmo banco.",
+    strategy: "A estratégia desta conta não existe mais. Edite a conta e escolha outra.",
+    process: "O programa não pôde ser aberto ou controlado. Verifique o caminho configurado.",
+    denied: "Senha mestra incorreta."
+};
+
+// Log Viewer Configuration
+export const LOG_LEVELS = ["ERROR", "WARN", "INFO", "DEBUG", "TRACE"];
+export const LOG_REFRESH_MS = 2000;
+
 // UI Configuration
 export const MODAL_CONFIG = {
     width: "28em",
@@ -0,0 +1,66 @@
+// Duplicate Accounts Window
+import { replyError, replyDetail } from "./utils.js";
+
+const { signal } = Reactor;
+
+const api = Window.this.parameters;
+
+const state = {
+    groups: signal([])
+};
+
+const refresh = () => {
+    state.groups.value = api.list();
+};
+
+const displayName = (account) => account.nickname || account.username;
+
+const lastUsed = (account) =>
+    account.last_used ? `usada em ${new Date(account.last_used).toLocaleDateString()}` : "nunca usada";
+
+const showError = (title, reply) =>
+    Window.this.modal(<error>
+        ❌ {title}<br/><br/>{replyError(reply)}
+        {replyDetail(reply) && <div style="margin-top: 8px; font-size: 0.85em; opacity: 0.7;">{replyDetail(reply)}</div>}
+    </error>);
+
+const DuplicateActions = {
+    // Manter `account` e mandar as demais do grupo para a lixeira
+    keep: (account, group) => {
+        const others = group.filter(other => other.id !== account.id);
+        const question = <question>
+            Manter {displayName(account)} e mover {others.length} conta(s) para a lixeira?
+            Servidores, tags, histórico de login e campos vazios são completados com os das outras.
+        </question>;
+        if (Window.this.modal(question) !== "yes") return;
+        const reply = api.merge(account.id, others.map(other => other.id));
+        if (!reply.ok) {
+            showError("Erro ao mesclar contas", reply);
+        }
+        refresh();
+    }
+};
+
+const DuplicateRow = ({ account, group }) => (
+    <div class="tool-row">
+        <span class="tool-name">{displayName(account)}</span>
+        <span class="tool-path">
+            {account.username} · servidores {account.servers.map(entry => entry.server).join(", ")} · {lastUsed(account)}
+        </span>
+        <button class="action-btn" title="Manter esta" onclick={() => DuplicateActions.keep(account, group)}>✅</button>
+    </div>
+);
+
+export const Duplicates = () => (
+    <div>
+        {state.groups.value.length === 0 ? <p class="empty-state">Nenhuma conta duplicada.</p> :
+            state.groups.value.map(group =>
+                <div class="tool-list">
+                    {group.map(account => <DuplicateRow account={account} group={group} />)}
+                </div>
+            )
+        }
+    </div>
+);
+
+refresh();
@@ -1,32 +1,74 @@
 // Modal Form Controller
 import { GAME_STRATEGY, SERVERS } from "./constants.js";
 
+// Linha de um servidor alem do padrao: servidor e personagem nele
+const ServerRow = ({ entry }) => (
+    <div class="server-row">
+        <select|dropdown class="server-id">
+            {!SERVERS.some(server => server.id === entry.server) &&
+                <option value={entry.server} selected>{entry.server}</option>}
+            {SERVERS.map(server =>
+                <option value={server.id} selected={server.id === entry.server}>{server.name} ({server.range})</option>
+            )}
+        </select>
+        <input|text class="server-nickname" value={entry.nickname || ""} placeholder="Personagem neste servidor" />
+        <button type="button" class="action-btn delete remove-server" title="Remover servidor">✖️</button>
+    </div>
+);
+
 export class FormController {
     constructor(formSelector = "form#account") {
         this.form = document.$(formSelector);
+        this.servers = document.$("div#servers");
+        this.strategy = Window.this.parameters?.default_strategy || GAME_STRATEGY;
+        // Servidores salvos da conta, para manter o last_used de cada um
+        this.entries = [];
     }
 
     getData() {
         const data = this.form.value;
-        data.strategy = GAME_STRATEGY;
+        data.strategy = this.strategy;
+        // Servidor padrao primeiro, cada servidor uma vez
+        const rows = this.servers.$$("div.server-row").map(row => ({
+            server: row.$("select.server-id").value,
+            nickname: row.$("input.server-nickname").value.trim()
+        }));
+        data.servers = [{ server: data.server }, ...rows]
+            .filter((row, index, all) => all.findIndex(other => other.server === row.server) === index)
+            .map(({ server, nickname }) => {
+                const entry = { ...this.entries.find(saved => saved.server === server), server };
+                if (nickname) entry.nickname = nickname;
+                else if (nickname === "") delete entry.nickname;
+                return entry;
+            });
         return data;
     }
 
     setData(data) {
-        const { username, password, server, nickname } = data;
+        // A senha nao vem do backend; em branco mantem a atual
+        const { username, strategy, server, servers, nickname, launcher } = data;
+        if (strategy) this.strategy = strategy;
         this.form.value = { 
             username, 
-            password, 
-            strategy: GAME_STRATEGY, 
+            strategy: this.strategy, 
             server, 
-            nickname 
+            nickname,
+            launcher
         };
+        this.entries = servers || [];
+        this.entries
+            .filter(entry => entry.server !== server)
+            .forEach(entry => this.addServer(entry));
+    }
+
+    addServer(entry = { server: SERVERS[0].id }) {
+        this.servers.append(<ServerRow entry={entry} />);
     }
 
     forceStrategy() {
         const strategyElement = document.$("[name='strategy']");
         if (strategyElement) {
-            strategyElement.value = GAME_STRATEGY;
+            strategyElement.value = this.strategy;
         }
     }
 
@@ -41,6 +83,15 @@ export const initializeForm = (isEditMode = false) => {
     const controller = new FormController();
     
     controller.onSubmit((data) => {
+        // Mesmo usuario e estrategia de outra conta: avisar e manter o formulario aberto
+        const duplicate = Window.this.parameters.find_duplicate(data);
+        if (duplicate) {
+            Window.this.modal(<warning>
+                ⚠️ A conta {duplicate.nickname || duplicate.username} já usa este usuário e estratégia.
+                Adicione o servidor a ela em vez de criar outra conta.
+            </warning>);
+            return;
+        }
         Window.this.close(data);
     });
 
@@ -54,4 +105,15 @@ export const initializeForm = (isEditMode = false) => {
             }
         }
     });
+
+    document.on("click", "button#add-server", () => controller.addServer());
+    document.on("click", "button.remove-server", (evt, button) => button.parentElement.remove());
+
+    // Mostrar a senha salva: chamada registrada no log e, se houver, protegida pela senha mestra
+    document.on("click", "button#reveal", () => {
+        const password = Window.this.parameters.reveal();
+        if (typeof password !== "string") return;
+        document.$("input[name='password']").value = password;
+        document.$("#revealed").content(<span>Senha: {password}</span>);
+    });
 };
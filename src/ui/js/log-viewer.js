@@ -0,0 +1,56 @@
+// Log Viewer Window
+import { LOG_LEVELS, LOG_REFRESH_MS } from "./constants.js";
+
+const { signal } = Reactor;
+
+const { fetch_logs, accounts } = Window.this.parameters;
+
+const state = {
+    level: signal("INFO"),
+    account: signal(""),
+    entries: signal([])
+};
+
+const refresh = () => {
+    state.entries.value = fetch_logs(state.level.value, state.account.value);
+};
+
+const formatTime = (timestamp) => new Date(timestamp).toLocaleTimeString();
+
+const Filters = () => (
+    <div class="log-filters">
+        <select|dropdown onchange={(e) => { state.level.value = e.target.value; refresh(); }}>
+            {LOG_LEVELS.map(level =>
+                <option value={level} selected={level === state.level.value}>{level}</option>
+            )}
+        </select>
+        <select|dropdown onchange={(e) => { state.account.value = e.target.value; refresh(); }}>
+            <option value="">Todas as contas</option>
+            {accounts.map(({ username, name }) =>
+                <option value={username} selected={username === state.account.value}>{name}</option>
+            )}
+        </select>
+        <button onclick={refresh}>🔄 Atualizar</button>
+    </div>
+);
+
+const LogLine = ({ entry }) => (
+    <div class={`log-line ${entry.level.toLowerCase()}`}>
+        <span class="log-time">{formatTime(entry.timestamp)}</span>
+        <span class="log-level">{entry.level}</span>
+        <span class="log-target">{entry.account ? `${entry.target} <${entry.account}>` : entry.target}</span>
+        <span class="log-message">{entry.message}</span>
+    </div>
+);
+
+export const LogViewer = () => (
+    <div>
+        <Filters />
+        <div class="log-list">
+            {state.entries.value.slice().reverse().map(entry => <LogLine entry={entry} />)}
+        </div>
+    </div>
+);
+
+refresh();
+setInterval(refresh, LOG_REFRESH_MS);
@@ -0,0 +1,28 @@
+// Master Password Modal (htm/master-password.htm)
+// mode "unlock" devolve { password }; mode "change" devolve { current, password }
+
+export const initializeMasterPasswordForm = () => {
+    const { mode, has_current } = Window.this.parameters;
+
+    document.on("click", "button#submit", () => {
+        const data = document.$("form#master").value;
+        if (mode === "change" && data.password !== data.confirm) {
+            Window.this.modal(<error>As senhas não conferem</error>);
+            return;
+        }
+        Window.this.close(mode === "change"
+            ? { current: data.current || "", password: data.password || "" }
+            : { password: data.password || "" });
+    });
+
+    document.on("ready", () => {
+        if (mode === "change") {
+            document.$("h2.modal-title").content("🔒 Senha mestra");
+            document.$("#hint").content(has_current
+                ? "Deixe a nova senha em branco para remover a senha mestra."
+                : "A senha mestra será pedida antes de mostrar a senha de uma conta.");
+            document.$("#confirm-field").attributes["hidden"] = undefined;
+            if (has_current) document.$("#current-field").attributes["hidden"] = undefined;
+        }
+    });
+};
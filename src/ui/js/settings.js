@@ -0,0 +1,56 @@
+// Settings Form Modal (htm/settings.htm)
+
+const TEXT_FIELDS = ["scripts_dir", "default_launcher", "flash_player"];
+
+export const initializeSettingsForm = () => {
+    document.on("click", "button#submit", () => {
+        const data = document.$("form#settings").value;
+        if (!data.user_agent.trim() || !(data.login_timeout > 0) || !(data.workers > 0)) {
+            Window.this.modal(<error>User agent, tempo limite e logins simultâneos são obrigatórios</error>);
+            return;
+        }
+        const settings = {
+            default_strategy: data.default_strategy,
+            user_agent: data.user_agent.trim(),
+            login_timeout: data.login_timeout,
+            workers: data.workers,
+            shutdown_grace: data.shutdown_grace || 0,
+            trash_days: data.trash_days || 0
+        };
+        // Campos vazios voltam ao padrao
+        for (const field of TEXT_FIELDS) {
+            const value = (data[field] || "").trim();
+            if (value) settings[field] = value;
+        }
+        Window.this.close(settings);
+    });
+
+    document.on("click", "button#master-password", () => {
+        Window.this.parameters.change_master_password();
+    });
+
+    document.on("ready", () => {
+        const { settings, strategy_list } = Window.this.parameters;
+        const select = document.$("select[name='default_strategy']");
+        for (const strategy of strategy_list) {
+            select.append(<option value={strategy}>{strategy}</option>);
+        }
+
+        document.$("form#settings").value = {
+            default_strategy: settings.default_strategy,
+            scripts_dir: settings.scripts_dir || "",
+            default_launcher: settings.default_launcher || "",
+            flash_player: settings.flash_player || "",
+            user_agent: settings.user_agent,
+            login_timeout: settings.login_timeout,
+            workers: settings.workers,
+            shutdown_grace: settings.shutdown_grace,
+            trash_days: settings.trash_days
+        };
+
+        const mode = settings.portable ? " (modo portátil)" : "";
+        document.$("#location").content(
+            <span>Dados: {settings.data_dir}{mode}<br/>Arquivo: {settings.settings_file}</span>
+        );
+    });
+};
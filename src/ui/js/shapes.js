@@ -0,0 +1,245 @@
+// Gerado a partir de src/ipc.rs. Nao edite: rode `UPDATE_SHAPES=1 cargo test --test ipc`.
+// Campos com `?` podem faltar; listas vazias tambem.
+
+/**
+ * @typedef {Object} Account
+ * @property {string} username
+ * @property {string} strategy
+ * @property {string} server
+ * @property {Object[]} servers
+ * @property {string} [nickname]
+ * @property {number} [last_used]
+ * @property {string[]} tags
+ * @property {string} [launcher]
+ * @property {boolean} has_password
+ */
+
+/**
+ * @typedef {Object} AccountForm
+ * @property {string} username
+ * @property {string} password
+ * @property {string} strategy
+ * @property {string} server
+ * @property {Object[]} servers
+ * @property {string} [nickname]
+ * @property {number} [last_used]
+ * @property {string[]} tags
+ * @property {string} [launcher]
+ */
+
+/**
+ * @typedef {Object} AccountEntry
+ * @property {string} username
+ * @property {string} strategy
+ * @property {string} server
+ * @property {Object[]} servers
+ * @property {string} [nickname]
+ * @property {number} [last_used]
+ * @property {string[]} tags
+ * @property {string} [launcher]
+ * @property {string} id
+ * @property {boolean} has_password
+ */
+
+/**
+ * @typedef {Object} ServerEntry
+ * @property {string} server
+ * @property {string} [nickname]
+ * @property {number} [last_used]
+ */
+
+/**
+ * @typedef {Object} DeletedAccount
+ * @property {string} username
+ * @property {string} strategy
+ * @property {string} server
+ * @property {Object[]} servers
+ * @property {string} [nickname]
+ * @property {number} [last_used]
+ * @property {string[]} tags
+ * @property {string} [launcher]
+ * @property {string} id
+ * @property {number} deleted_at
+ */
+
+/**
+ * @typedef {Object} Tool
+ * @property {string} name
+ * @property {string} path
+ * @property {string[]} args
+ * @property {string} [cwd]
+ * @property {string} [icon]
+ * @property {boolean} start_with_game
+ * @property {boolean} keep_alive
+ * @property {string} id
+ * @property {boolean} running
+ */
+
+/**
+ * @typedef {Object} ToolForm
+ * @property {string} name
+ * @property {string} path
+ * @property {string[]} args
+ * @property {string} [cwd]
+ * @property {string} [icon]
+ * @property {boolean} start_with_game
+ * @property {boolean} keep_alive
+ */
+
+/**
+ * @typedef {Object} Settings
+ * @property {string} [scripts_dir]
+ * @property {string} [flash_player]
+ * @property {string} [default_launcher]
+ * @property {string} default_strategy
+ * @property {string} user_agent
+ * @property {number} login_timeout
+ * @property {number} workers
+ * @property {number} shutdown_grace
+ * @property {number} trash_days
+ * @property {boolean} master_password
+ * @property {boolean} portable
+ * @property {string} data_dir
+ * @property {string} settings_file
+ */
+
+/**
+ * @typedef {Object} SettingsForm
+ * @property {string} [scripts_dir]
+ * @property {string} [flash_player]
+ * @property {string} [default_launcher]
+ * @property {string} default_strategy
+ * @property {string} user_agent
+ * @property {number} login_timeout
+ * @property {number} workers
+ * @property {number} shutdown_grace
+ * @property {number} trash_days
+ */
+
+/**
+ * @typedef {Object} Game
+ * @property {number} pid
+ * @property {number} started_at
+ * @property {string} launcher
+ */
+
+/**
+ * @typedef {Object} LogEntry
+ * @property {number} timestamp
+ * @property {string} level
+ * @property {string} target
+ * @property {string} [account]
+ * @property {string} message
+ */
+
+export const SHAPES = {
+    Account: {
+        username: "string",
+        strategy: "string",
+        server: "string",
+        servers: "Object[]",
+        nickname: "string?",
+        last_used: "number?",
+        tags: "string[]",
+        launcher: "string?",
+        has_password: "boolean"
+    },
+    AccountForm: {
+        username: "string",
+        password: "string",
+        strategy: "string",
+        server: "string",
+        servers: "Object[]",
+        nickname: "string?",
+        last_used: "number?",
+        tags: "string[]",
+        launcher: "string?"
+    },
+    AccountEntry: {
+        username: "string",
+        strategy: "string",
+        server: "string",
+        servers: "Object[]",
+        nickname: "string?",
+        last_used: "number?",
+        tags: "string[]",
+        launcher: "string?",
+        id: "string",
+        has_password: "boolean"
+    },
+    ServerEntry: {
+        server: "string",
+        nickname: "string?",
+        last_used: "number?"
+    },
+    DeletedAccount: {
+        username: "string",
+        strategy: "string",
+        server: "string",
+        servers: "Object[]",
+        nickname: "string?",
+        last_used: "number?",
+        tags: "string[]",
+        launcher: "string?",
+        id: "string",
+        deleted_at: "number"
+    },
+    Tool: {
+        name: "string",
+        path: "string",
+        args: "string[]",
+        cwd: "string?",
+        icon: "string?",
+        start_with_game: "boolean",
+        keep_alive: "boolean",
+        id: "string",
+        running: "boolean"
+    },
+    ToolForm: {
+        name: "string",
+        path: "string",
+        args: "string[]",
+        cwd: "string?",
+        icon: "string?",
+        start_with_game: "boolean",
+        keep_alive: "boolean"
+    },
+    Settings: {
+        scripts_dir: "string?",
+        flash_player: "string?",
+        default_launcher: "string?",
+        default_strategy: "string",
+        user_agent: "string",
+        login_timeout: "number",
+        workers: "number",
+        shutdown_grace: "number",
+        trash_days: "number",
+        master_password: "boolean",
+        portable: "boolean",
+        data_dir: "string",
+        settings_file: "string"
+    },
+    SettingsForm: {
+        scripts_dir: "string?",
+        flash_player: "string?",
+        default_launcher: "string?",
+        default_strategy: "string",
+        user_agent: "string",
+        login_timeout: "number",
+        workers: "number",
+        shutdown_grace: "number",
+        trash_days: "number"
+    },
+    Game: {
+        pid: "number",
+        started_at: "number",
+        launcher: "string"
+    },
+    LogEntry: {
+        timestamp: "number",
+        level: "string",
+        target: "string",
+        account: "string?",
+        message: "string"
+    }
+};
@@ -0,0 +1,41 @@
+// Tool Form Modal (htm/tool-form.htm)
+
+// "--a b" <-> ["--a", "b"]; aspas agrupam argumentos com espacos
+const parseArgs = (text) =>
+    (text.match(/"[^"]*"|\S+/g) || []).map(arg => arg.replace(/^"|"$/g, ""));
+const formatArgs = (args) =>
+    (args || []).map(arg => /\s/.test(arg) ? `"${arg}"` : arg).join(" ");
+
+export const initializeToolForm = () => {
+    document.on("click", "button#submit", () => {
+        const data = document.$("form#tool").value;
+        if (!data.name.trim() || !data.path.trim()) {
+            Window.this.modal(<error>Nome e caminho são obrigatórios</error>);
+            return;
+        }
+        Window.this.close({
+            name: data.name.trim(),
+            path: data.path.trim(),
+            args: parseArgs(data.args || ""),
+            cwd: data.cwd.trim() || undefined,
+            icon: data.icon.trim() || undefined,
+            start_with_game: !!data.start_with_game,
+            keep_alive: !!data.keep_alive
+        });
+    });
+
+    document.on("ready", () => {
+        const { tool } = Window.this.parameters;
+        if (tool) {
+            document.$("form#tool").value = {
+                name: tool.name,
+                path: tool.path,
+                args: formatArgs(tool.args),
+                cwd: tool.cwd || "",
+                icon: tool.icon || "",
+                start_with_game: tool.start_with_game,
+                keep_alive: tool.keep_alive
+            };
+        }
+    });
+};
@@ -0,0 +1,75 @@
+// Tools Manager Window
+import { replyError, replyDetail } from "./utils.js";
+
+const { signal } = Reactor;
+
+const api = Window.this.parameters;
+
+const state = {
+    tools: signal([])
+};
+
+const refresh = () => {
+    state.tools.value = api.list();
+};
+
+const showForm = (tool) => Window.this.modal({
+    url: __DIR__ + "../htm/tool-form.htm",
+    parameters: { tool }
+});
+
+const showError = (title, reply) =>
+    Window.this.modal(<error>
+        ❌ {title}<br/><br/>{replyError(reply)}
+        {replyDetail(reply) && <div style="margin-top: 8px; font-size: 0.85em; opacity: 0.7;">{replyDetail(reply)}</div>}
+    </error>);
+
+const ToolActions = {
+    add: () => {
+        const data = showForm(undefined);
+        const reply = data && api.add(data);
+        if (reply && !reply.ok) {
+            showError("Erro ao adicionar ferramenta", reply);
+        }
+        refresh();
+    },
+
+    edit: (tool) => {
+        const data = showForm(tool);
+        const reply = data && api.replace(tool.id, data);
+        if (reply && !reply.ok) {
+            showError("Erro ao atualizar ferramenta", reply);
+        }
+        refresh();
+    },
+
+    remove: (tool) => {
+        if (Window.this.modal(<question>Remover {tool.name}?</question>) !== "yes") return;
+        const reply = api.remove(tool.id);
+        if (!reply.ok) {
+            showError("Erro ao remover ferramenta", reply);
+        }
+        refresh();
+    }
+};
+
+const ToolRow = ({ tool }) => (
+    <div class="tool-row">
+        <span class="tool-icon">{tool.icon || "🔧"}</span>
+        <span class="tool-name">{tool.name}</span>
+        <span class="tool-path">{tool.start_with_game ? `${tool.path} · abre com o jogo` : tool.path}</span>
+        <button class="action-btn" onclick={() => ToolActions.edit(tool)}>✏️</button>
+        <button class="action-btn delete" onclick={() => ToolActions.remove(tool)}>🗑️</button>
+    </div>
+);
+
+export const ToolsManager = () => (
+    <div>
+        <div class="tool-list">
+            {state.tools.value.map(tool => <ToolRow tool={tool} />)}
+        </div>
+        <button onclick={ToolActions.add}>➕ Adicionar Ferramenta</button>
+    </div>
+);
+
+refresh();
@@ -0,0 +1,84 @@
+// Recently Deleted Accounts Window
+import { replyError, replyDetail } from "./utils.js";
+
+const { signal } = Reactor;
+
+const api = Window.this.parameters;
+
+const state = {
+    accounts: signal([])
+};
+
+const refresh = () => {
+    state.accounts.value = api.list();
+};
+
+const displayName = (account) => account.nickname || account.username;
+
+const DAY = 24 * 60 * 60 * 1000;
+
+const expiry = (account) => {
+    if (!api.trash_days) return "mantida até ser apagada";
+    const days = Math.ceil((account.deleted_at + api.trash_days * DAY - Date.now()) / DAY);
+    return days > 1 ? `apagada em ${days} dias` : "apagada em breve";
+};
+
+const showError = (title, reply) =>
+    Window.this.modal(<error>
+        ❌ {title}<br/><br/>{replyError(reply)}
+        {replyDetail(reply) && <div style="margin-top: 8px; font-size: 0.85em; opacity: 0.7;">{replyDetail(reply)}</div>}
+    </error>);
+
+const TrashActions = {
+    restore: (account) => {
+        const reply = api.restore(account.id);
+        if (!reply.ok) {
+            showError("Erro ao restaurar conta", reply);
+        }
+        refresh();
+    },
+
+    purge: (account) => {
+        if (Window.this.modal(<question>Apagar {displayName(account)} definitivamente?</question>) !== "yes") return;
+        const reply = api.purge(account.id);
+        if (!reply.ok) {
+            showError("Erro ao apagar conta", reply);
+        }
+        refresh();
+    },
+
+    empty: () => {
+        if (Window.this.modal(<question>Apagar definitivamente todas as contas da lixeira?</question>) !== "yes") return;
+        const reply = api.empty();
+        if (!reply.ok) {
+            showError("Erro ao esvaziar a lixeira", reply);
+        }
+        refresh();
+    }
+};
+
+const TrashRow = ({ account }) => (
+    <div class="tool-row">
+        <span class="tool-name">{displayName(account)}</span>
+        <span class="tool-path">
+            {new Date(account.deleted_at).toLocaleDateString()} · {expiry(account)}
+        </span>
+        <button class="action-btn" title="Restaurar" onclick={() => TrashActions.restore(account)}>↩️</button>
+        <button class="action-btn delete" title="Apagar definitivamente" onclick={() => TrashActions.purge(account)}>🗑️</button>
+    </div>
+);
+
+export const Trash = () => (
+    <div>
+        {state.accounts.value.length === 0 ? <p class="empty-state">A lixeira está vazia.</p> :
+            <div>
+                <div class="tool-list">
+                    {state.accounts.value.map(account => <TrashRow account={account} />)}
+                </div>
+                <button onclick={TrashActions.empty}>🗑️ Esvaziar Lixeira</button>
+            </div>
+        }
+    </div>
+);
+
+refresh();
@@ -2,6 +2,52 @@
  * Utility functions for account management
  */
 
+import { ERROR_MESSAGES } from "./constants.js";
+
+/**
+ * Call the backend, which always replies { ok: true, data } or
+ * { ok: false, code, message }
+ * @param {string} name - Script call name
+ * @param {...*} args - Call arguments
+ * @returns {Object} Reply
+ */
+export const call = (name, ...args) => Window.this.xcall(name, ...args);
+
+/**
+ * Data of a reply, or a fallback when the call failed
+ * @param {Object} reply - Backend reply
+ * @param {*} fallback - Value for failed calls
+ * @returns {*} Data
+ */
+export const replyData = (reply, fallback) => reply.ok ? reply.data : fallback;
+
+/**
+ * Copy the fields a shape from shapes.js declares, dropping anything else
+ * @param {Object} data - Source object, e.g. form data
+ * @param {Object} shape - Entry of SHAPES
+ * @returns {Object} Object with only the shape's fields
+ */
+export const pickFields = (data, shape) => Object.fromEntries(
+    Object.keys(shape)
+        .filter(field => data[field] !== undefined)
+        .map(field => [field, data[field]])
+);
+
+/**
+ * Message for the user about a failed reply, based on its code
+ * @param {Object} reply - Backend reply
+ * @returns {string} Message
+ */
+export const replyError = (reply) => ERROR_MESSAGES[reply.code] || reply.message || "";
+
+/**
+ * Backend detail of a failed reply, shown below `replyError`; empty when
+ * it is already the message itself
+ * @param {Object} reply - Backend reply
+ * @returns {string} Detail
+ */
+export const replyDetail = (reply) => (ERROR_MESSAGES[reply.code] && reply.message) || "";
+
 /**
  * Get display name for an account
  * @param {Object} account - Account object
@@ -12,7 +58,8 @@ export const getAccountDisplayName = (account) => {
 };
 
 /**
- * Filter accounts by search text
+ * Filter accounts by search text, matching the display name or the
+ * character name on any of the account's servers
  * @param {Object} accounts - Accounts object
  * @param {string} searchText - Search text
  * @returns {Array} Filtered accounts array
@@ -23,8 +70,8 @@ export const filterAccounts = (accounts, searchText) => {
     
     if (search) {
         accountsArray = accountsArray.filter(([id, account]) => {
-            const name = getAccountDisplayName(account).toLowerCase();
-            return name.includes(search);
+            const names = [getAccountDisplayName(account), ...(account.servers || []).map(entry => entry.nickname || "")];
+            return names.some(name => name.toLowerCase().includes(search));
         });
     }
     
@@ -82,12 +129,3 @@ export const debounce = (func, wait = 300) => {
         timeout = setTimeout(later, wait);
     };
 };
-
-/**
- * Check if URL is valid game URL
- * @param {string} url - URL to check
- * @returns {boolean} Is valid
- */
-export const isValidGameUrl = (url) => {
-    return url.startsWith("http") || url.startsWith("roadclient://");
-};
@@ -0,0 +1,17 @@
+// Onboarding Modal (htm/welcome.htm), devolve "add", "import" ou nada
+
+export const initializeWelcome = () => {
+    document.on("click", "button#next", () => {
+        document.$("section#intro").attributes["hidden"] = true;
+        document.$("section#start").attributes["hidden"] = undefined;
+    });
+    document.on("click", "button#add", () => Window.this.close("add"));
+    document.on("click", "button#import", () => Window.this.close("import"));
+    document.on("click", "button#skip", () => Window.this.close());
+
+    document.on("ready", () => {
+        const { data_dir, portable } = Window.this.parameters;
+        const mode = portable ? " (modo portátil)" : "";
+        document.$("#location").content(<span>Suas contas ficam em {data_dir}{mode}.</span>);
+    });
+};
@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use ddtank_rs::ipc::{self, Shape};
+use ddtank_rs::UserInfo;
+
+#[test]
+fn shapes_js_is_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/ui/js/shapes.js");
+    let generated = ipc::shapes_js();
+    if std::env::var_os("UPDATE_SHAPES").is_some() {
+        std::fs::write(&path, &generated).unwrap();
+    }
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "src/ui/js/shapes.js is stale, run `UPDATE_SHAPES=1 cargo test --test ipc`"
+    );
+}
+
+#[test]
+fn accounts_never_carry_the_password() {
+    let user = UserInfo {
+        username: "usuario".to_owned(),
+        password: "segredo".to_owned(),
+        strategy: "337.lua".to_owned(),
+        server: "10000".to_owned(),
+        servers: Vec::new(),
+        nickname: None,
+        last_used: Some(1_700_000_000_000),
+        tags: vec!["farm".to_owned()],
+        launcher: None,
+    };
+    let account = ipc::account(&user).unwrap();
+    assert!(account.get("password").is_none());
+    assert_eq!(account["has_password"], true);
+    assert_eq!(account["last_used"], 1_700_000_000_000u64);
+
+    let shapes = ipc::shapes();
+    let (_, shape) = shapes.iter().find(|(name, _)| *name == "Account").unwrap();
+    let Shape::Object(fields) = shape else { panic!("Account is not an object") };
+    assert!(fields.iter().all(|(name, _)| name != "password"));
+    assert!(fields.contains(&("last_used".to_owned(), Shape::Optional(Box::new(Shape::Number)))));
+}
@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use ddtank_rs::sar;
+
+fn committed_archive() -> Vec<u8> {
+    std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/ui.rc")).unwrap()
+}
+
+/// Length of the magic, the name tree and the entry count.
+fn index_len(archive: &[u8]) -> usize {
+    let nodes = u32::from_le_bytes(archive[4..8].try_into().unwrap()) as usize;
+    8 + nodes * 8 + 4
+}
+
+#[test]
+fn committed_ui_rc_round_trips() {
+    let committed = committed_archive();
+    let files = sar::unpack(&committed).unwrap();
+    assert!(files.iter().any(|(name, _)| name == "index.htm"));
+    assert!(files.iter().any(|(name, _)| name == "img/logo.png"));
+
+    // packfolder's LZF output is not reproducible, but the name index is
+    let repacked = sar::pack(&files).unwrap();
+    let len = index_len(&committed);
+    assert_eq!(committed[..len], repacked[..len]);
+    assert_eq!(sar::unpack(&repacked).unwrap(), files);
+}
+
+#[test]
+fn packs_ui_folder() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/ui");
+    let files = sar::unpack(&sar::pack_dir(&dir).unwrap()).unwrap();
+    assert!(!files.is_empty());
+    for (name, data) in files {
+        let on_disk = std::fs::read_dir(dir.join(Path::new(&name).parent().unwrap()))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.file_name().unwrap().to_string_lossy().to_lowercase() == Path::new(&name).file_name().unwrap().to_string_lossy())
+            .unwrap_or_else(|| panic!("{} not in src/ui", name));
+        assert_eq!(std::fs::read(on_disk).unwrap(), data, "{}", name);
+    }
+}
+
+#[test]
+fn rejects_garbage() {
+    assert!(sar::unpack(b"not an archive").is_err());
+    assert!(sar::pack(&[("a".into(), vec![1]), ("A".into(), vec![2])]).is_err());
+}
+
+#[test]
+fn rejects_wrong_expanded_length() {
+    let packed = sar::pack(&[("a.txt".into(), b"abcabcabcabcabcabcabcabc".repeat(8))]).unwrap();
+    let original_at = index_len(&packed) + 8;
+    assert_ne!(packed[original_at..original_at + 4], [0; 4], "entry not compressed");
+    for original in [u32::MAX, 8 * 24 - 1, 8 * 24 + 1] {
+        let mut archive = packed.clone();
+        archive[original_at..original_at + 4].copy_from_slice(&original.to_le_bytes());
+        assert!(sar::unpack(&archive).is_err(), "{}", original);
+    }
+}